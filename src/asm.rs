@@ -0,0 +1,477 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::cpu::instruction::Instruction;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize
+}
+
+impl<'a> Token<'a> {
+    fn error<S: Into<String>>(&self, message: S) -> AsmError {
+        AsmError { line: self.line, column: self.column, message: message.into() }
+    }
+
+    fn upper(&self) -> String {
+        self.text.to_ascii_uppercase()
+    }
+}
+
+struct Statement<'a> {
+    addr: usize,
+    mnemonic: Token<'a>,
+    operands: Vec<Token<'a>>
+}
+
+enum Operand {
+    Reg(u8),
+    Range(u8, u8),
+    Keyword(&'static str),
+    Value
+}
+
+const KEYWORDS: [&str; 11] = ["I", "[I]", "DT", "ST", "K", "F", "HF", "B", "R", "AUDIO", "PITCH"];
+
+fn parse_register(text: &str) -> Option<u8> {
+    let text = text.as_bytes();
+
+    if text.len() == 2 && (text[0] == b'V' || text[0] == b'v') {
+        (text[1] as char).to_digit(16).map(|r| r as u8)
+    } else {
+        None
+    }
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let lower = text.to_ascii_lowercase();
+
+    if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        _ => false
+    }
+}
+
+/// Splits `text` on commas outside of string literals, trimming each part
+/// and tracking the column it started at.
+fn split_operands(text: &str, line: usize, column: usize) -> Vec<Token<'_>> {
+    let mut operands = Vec::new();
+    let mut start = 0;
+    let mut in_string = false;
+
+    for (i, c) in text.char_indices().chain(std::iter::once((text.len(), ','))) {
+        match c {
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                let part = &text[start..i];
+                let trimmed = part.trim_start();
+                let offset = part.len() - trimmed.len();
+
+                operands.push(Token { text: trimmed.trim_end(), line, column: column + start + offset });
+                start = i + 1;
+            },
+            _ => ()
+        }
+    }
+
+    if operands.len() == 1 && operands[0].text.is_empty() {
+        operands.clear();
+    }
+
+    operands
+}
+
+struct Assembler<'a> {
+    symbols: HashMap<&'a str, i64>,
+    statements: Vec<Statement<'a>>,
+    origin: usize,
+    end: usize
+}
+
+impl<'a> Assembler<'a> {
+    fn new(origin: usize) -> Assembler<'a> {
+        Assembler { symbols: HashMap::new(), statements: Vec::new(), origin, end: origin }
+    }
+
+    fn define(&mut self, name: Token<'a>, value: i64) -> Result<(), AsmError> {
+        if !is_identifier(name.text) || parse_register(name.text).is_some() ||
+            KEYWORDS.contains(&name.upper().as_str()) {
+            return Err(name.error(format!("invalid symbol name `{}`", name.text)));
+        }
+
+        if self.symbols.insert(name.text, value).is_some() {
+            return Err(name.error(format!("duplicate symbol `{}`", name.text)));
+        }
+
+        Ok(())
+    }
+
+    fn eval(&self, token: &Token) -> Result<i64, AsmError> {
+        let mut total: i64 = 0;
+        let mut sign = 1;
+        let mut start = 0;
+        let text = token.text;
+
+        for (i, c) in text.char_indices().chain(std::iter::once((text.len(), '+'))) {
+            if (c == '+' || c == '-') && (i == text.len() || !text[start..i].trim().is_empty()) {
+                let term = text[start..i].trim();
+                let column = token.column + start + (text[start..i].len() - text[start..i].trim_start().len());
+                let term_token = Token { text: term, line: token.line, column };
+
+                let value = match parse_number(term) {
+                    Some(value) => value,
+                    None if is_identifier(term) => match self.symbols.get(term) {
+                        Some(value) => *value,
+                        None => return Err(term_token.error(format!("undefined symbol `{}`", term)))
+                    },
+                    None => return Err(term_token.error(format!("invalid expression `{}`", term)))
+                };
+
+                total = value.checked_mul(sign).and_then(|value| total.checked_add(value))
+                    .ok_or_else(|| term_token.error(format!("expression `{}` overflows", text.trim())))?;
+                sign = if c == '-' { -1 } else { 1 };
+                start = i + 1;
+            }
+        }
+
+        Ok(total)
+    }
+
+    fn operand(&self, token: &Token) -> Result<Operand, AsmError> {
+        let upper = token.upper();
+
+        if let Some(r) = parse_register(token.text) {
+            return Ok(Operand::Reg(r));
+        }
+
+        if let Some(keyword) = KEYWORDS.iter().find(|k| **k == upper) {
+            return Ok(Operand::Keyword(keyword));
+        }
+
+        let parts: Vec<&str> = token.text.split('-').map(|p| p.trim()).collect();
+
+        if parts.len() == 2 {
+            if let (Some(x), Some(y)) = (parse_register(parts[0]), parse_register(parts[1])) {
+                return Ok(Operand::Range(x, y));
+            }
+        }
+
+        self.eval(token)?;
+        Ok(Operand::Value)
+    }
+
+    fn value(&self, token: &Token, max: i64, what: &str) -> Result<i64, AsmError> {
+        let value = self.eval(token)?;
+
+        if value < 0 || value > max {
+            Err(token.error(format!("value {} out of range for {}", value, what)))
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Size in bytes of the statement, used to lay out labels before encoding.
+    fn size(&self, mnemonic: &Token, operands: &[Token]) -> usize {
+        match mnemonic.upper().as_str() {
+            "DB" => {
+                let mut size = 0;
+                for operand in operands {
+                    size += if operand.text.starts_with('"') { operand.text.len().saturating_sub(2) } else { 1 };
+                }
+                size
+            },
+            "DW" => 2 * operands.len(),
+            "SPRITE" => {
+                let width = operands.first().map(|o| o.text.trim_matches('"').len()).unwrap_or(0);
+                if width > 8 { 2 } else { 1 }
+            },
+            "LD" if operands.len() == 2 && operands[0].upper() == "I" &&
+                operands[1].upper().starts_with("LONG ") => 4,
+            _ => 2
+        }
+    }
+
+    fn parse_line(&mut self, text: &'a str, line: usize) -> Result<(), AsmError> {
+        let code = match text.find(';') {
+            Some(i) => &text[..i],
+            None => text
+        };
+
+        let mut rest = code;
+        let mut column = 1;
+
+        // Labels
+        while let Some(i) = rest.find(':') {
+            let label = rest[..i].trim();
+
+            if label.contains(char::is_whitespace) || label.contains('"') {
+                break;
+            }
+
+            let offset = rest.len() - rest.trim_start().len();
+            let token = Token { text: label, line, column: column + offset };
+            let addr = self.end as i64;
+            self.define(token, addr)?;
+
+            column += i + 1;
+            rest = &rest[i + 1..];
+        }
+
+        let trimmed = rest.trim_start();
+        if trimmed.is_empty() {
+            return Ok(());
+        }
+
+        column += rest.len() - trimmed.len();
+        let rest = trimmed.trim_end();
+
+        let (first, tail) = match rest.find(char::is_whitespace) {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "")
+        };
+
+        let first = Token { text: first, line, column };
+        let tail_trimmed = tail.trim_start();
+        let tail_column = column + first.text.len() + (tail.len() - tail_trimmed.len());
+
+        // Constants: NAME EQU value
+        let (second, after) = match tail_trimmed.find(char::is_whitespace) {
+            Some(i) => (&tail_trimmed[..i], &tail_trimmed[i..]),
+            None => (tail_trimmed, "")
+        };
+
+        if second.eq_ignore_ascii_case("EQU") {
+            let value_text = after.trim_start();
+            let value_column = tail_column + second.len() + (after.len() - value_text.len());
+            let value = self.eval(&Token { text: value_text, line, column: value_column })?;
+            return self.define(first, value);
+        }
+
+        let operands = split_operands(tail_trimmed, line, tail_column);
+
+        if first.upper() == "ORG" {
+            if operands.len() != 1 {
+                return Err(first.error("ORG takes one operand"));
+            }
+
+            let addr = self.value(&operands[0], 0xFFFF, "address")? as usize;
+
+            if addr < self.origin {
+                return Err(operands[0].error(format!("ORG 0x{:X} is below the origin 0x{:X}", addr, self.origin)));
+            }
+
+            self.end = addr;
+            return Ok(());
+        }
+
+        let size = self.size(&first, &operands);
+        self.statements.push(Statement { addr: self.end, mnemonic: first, operands });
+        self.end += size;
+
+        Ok(())
+    }
+
+    fn encode(&self, stmt: &Statement) -> Result<Vec<u8>, AsmError> {
+        let ops = &stmt.operands;
+        let mnemonic = stmt.mnemonic.upper();
+
+        match mnemonic.as_str() {
+            "DB" => {
+                let mut bytes = Vec::new();
+                for op in ops {
+                    if op.text.starts_with('"') && op.text.ends_with('"') && op.text.len() >= 2 {
+                        bytes.extend_from_slice(&op.text.as_bytes()[1..op.text.len() - 1]);
+                    } else {
+                        bytes.push(self.value(op, 0xFF, "byte")? as u8);
+                    }
+                }
+                return Ok(bytes);
+            },
+            "DW" => {
+                let mut bytes = Vec::new();
+                for op in ops {
+                    let v = self.value(op, 0xFFFF, "word")? as u16;
+                    bytes.push((v >> 8) as u8);
+                    bytes.push(v as u8);
+                }
+                return Ok(bytes);
+            },
+            "SPRITE" => {
+                if ops.len() != 1 || !ops[0].text.starts_with('"') || !ops[0].text.ends_with('"') {
+                    return Err(stmt.mnemonic.error("SPRITE takes one quoted row such as \"##..##..\""));
+                }
+
+                let row = ops[0].text.trim_matches('"');
+                if row.len() > 16 {
+                    return Err(ops[0].error("sprite rows can be at most 16 pixels wide"));
+                }
+
+                let width = if row.len() > 8 { 16 } else { 8 };
+                let mut bits: u16 = 0;
+
+                for (x, c) in row.chars().enumerate() {
+                    match c {
+                        '#' | '1' | 'X' | 'x' => bits |= 1 << (width - 1 - x),
+                        '.' | '0' | ' ' | '_' => (),
+                        _ => return Err(ops[0].error(format!("invalid sprite pixel `{}`", c)))
+                    }
+                }
+
+                return Ok(if width == 16 { vec![(bits >> 8) as u8, bits as u8] } else { vec![bits as u8] });
+            },
+            "LD" if ops.len() == 2 && ops[0].upper() == "I" && ops[1].upper().starts_with("LONG ") => {
+                let text = ops[1].text[4..].trim_start();
+                let column = ops[1].column + ops[1].text.len() - text.len();
+                let token = Token { text, line: ops[1].line, column };
+                let addr = self.value(&token, 0xFFFF, "address")? as u16;
+                return Ok(vec![0xF0, 0x00, (addr >> 8) as u8, addr as u8]);
+            },
+            _ => ()
+        }
+
+        let inst = self.instruction(stmt)?;
+        let opcode = inst.encode();
+        Ok(vec![(opcode >> 8) as u8, opcode as u8])
+    }
+
+    fn instruction(&self, stmt: &Statement) -> Result<Instruction, AsmError> {
+        let mnemonic = stmt.mnemonic.upper();
+        let tokens = &stmt.operands;
+        let ops = tokens.iter().map(|t| self.operand(t)).collect::<Result<Vec<Operand>, AsmError>>()?;
+
+        let byte = |i: usize| self.value(&tokens[i], 0xFF, "byte").map(|v| v as u8);
+        let addr = |i: usize| self.value(&tokens[i], 0xFFF, "address").map(|v| v as u16);
+        let nibble = |i: usize| self.value(&tokens[i], 0xF, "nibble").map(|v| v as u8);
+
+        use self::Operand::*;
+
+        let inst = match (mnemonic.as_str(), &ops[..]) {
+            ("CLS", []) => Instruction::Cls,
+            ("RET", []) => Instruction::Ret,
+            ("SCR", []) => Instruction::ScrollRight,
+            ("SCL", []) => Instruction::ScrollLeft,
+            ("EXIT", []) => Instruction::Exit,
+            ("LOW", []) => Instruction::Low,
+            ("HIGH", []) => Instruction::High,
+            ("SCD", [Value]) => Instruction::ScrollDown { n: nibble(0)? },
+            ("SCU", [Value]) => Instruction::ScrollUp { n: nibble(0)? },
+            ("SYS", [Value]) => Instruction::Sys { addr: addr(0)? },
+            ("JP", [Value]) => Instruction::Jp { addr: addr(0)? },
+            ("JP", [Reg(0), Value]) => {
+                let addr = addr(1)?;
+                Instruction::JpV0 { x: (addr >> 8) as u8, addr }
+            },
+            ("CALL", [Value]) => Instruction::Call { addr: addr(0)? },
+            ("SE", [Reg(x), Value]) => Instruction::Se { x: *x, byte: byte(1)? },
+            ("SE", [Reg(x), Reg(y)]) => Instruction::SeReg { x: *x, y: *y },
+            ("SNE", [Reg(x), Value]) => Instruction::Sne { x: *x, byte: byte(1)? },
+            ("SNE", [Reg(x), Reg(y)]) => Instruction::SneReg { x: *x, y: *y },
+            ("LD", [Reg(x), Value]) => Instruction::Ld { x: *x, byte: byte(1)? },
+            ("LD", [Reg(x), Reg(y)]) => Instruction::LdReg { x: *x, y: *y },
+            ("LD", [Keyword("I"), Value]) => Instruction::LdI { addr: addr(1)? },
+            ("LD", [Reg(x), Keyword("DT")]) => Instruction::LdVxDt { x: *x },
+            ("LD", [Reg(x), Keyword("K")]) => Instruction::LdVxK { x: *x },
+            ("LD", [Keyword("DT"), Reg(x)]) => Instruction::LdDt { x: *x },
+            ("LD", [Keyword("ST"), Reg(x)]) => Instruction::LdSt { x: *x },
+            ("LD", [Keyword("F"), Reg(x)]) => Instruction::LdF { x: *x },
+            ("LD", [Keyword("HF"), Reg(x)]) => Instruction::LdHf { x: *x },
+            ("LD", [Keyword("B"), Reg(x)]) => Instruction::LdB { x: *x },
+            ("LD", [Keyword("[I]"), Reg(x)]) => Instruction::StoreRegs { x: *x },
+            ("LD", [Reg(x), Keyword("[I]")]) => Instruction::LoadRegs { x: *x },
+            ("LD", [Keyword("R"), Reg(x)]) => Instruction::StoreFlags { x: *x },
+            ("LD", [Reg(x), Keyword("R")]) => Instruction::LoadFlags { x: *x },
+            ("LD", [Keyword("[I]"), Range(x, y)]) => Instruction::StoreRange { x: *x, y: *y },
+            ("LD", [Range(x, y), Keyword("[I]")]) => Instruction::LoadRange { x: *x, y: *y },
+            ("LD", [Keyword("AUDIO"), Keyword("[I]")]) => Instruction::LdAudio,
+            ("LD", [Keyword("PITCH"), Reg(x)]) => Instruction::LdPitch { x: *x },
+            ("ADD", [Reg(x), Value]) => Instruction::Add { x: *x, byte: byte(1)? },
+            ("ADD", [Reg(x), Reg(y)]) => Instruction::AddReg { x: *x, y: *y },
+            ("ADD", [Keyword("I"), Reg(x)]) => Instruction::AddI { x: *x },
+            ("OR", [Reg(x), Reg(y)]) => Instruction::Or { x: *x, y: *y },
+            ("AND", [Reg(x), Reg(y)]) => Instruction::And { x: *x, y: *y },
+            ("XOR", [Reg(x), Reg(y)]) => Instruction::Xor { x: *x, y: *y },
+            ("SUB", [Reg(x), Reg(y)]) => Instruction::Sub { x: *x, y: *y },
+            ("SUBN", [Reg(x), Reg(y)]) => Instruction::Subn { x: *x, y: *y },
+            ("SHR", [Reg(x)]) => Instruction::Shr { x: *x, y: *x },
+            ("SHR", [Reg(x), Reg(y)]) => Instruction::Shr { x: *x, y: *y },
+            ("SHL", [Reg(x)]) => Instruction::Shl { x: *x, y: *x },
+            ("SHL", [Reg(x), Reg(y)]) => Instruction::Shl { x: *x, y: *y },
+            ("RND", [Reg(x), Value]) => Instruction::Rnd { x: *x, byte: byte(1)? },
+            ("DRW", [Reg(x), Reg(y), Value]) => Instruction::Drw { x: *x, y: *y, n: nibble(2)? },
+            ("SKP", [Reg(x)]) => Instruction::Skp { x: *x },
+            ("SKNP", [Reg(x)]) => Instruction::Sknp { x: *x },
+            ("PLANE", [Value]) => Instruction::Plane { n: nibble(0)? },
+            _ => {
+                let known = ["CLS", "RET", "SCR", "SCL", "EXIT", "LOW", "HIGH", "SCD", "SCU", "SYS", "JP", "CALL",
+                    "SE", "SNE", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SUBN", "SHR", "SHL", "RND", "DRW",
+                    "SKP", "SKNP", "PLANE"];
+
+                return if known.contains(&mnemonic.as_str()) {
+                    Err(stmt.mnemonic.error(format!("invalid operands for {}", mnemonic)))
+                } else {
+                    Err(stmt.mnemonic.error(format!("unknown mnemonic `{}`", stmt.mnemonic.text)))
+                };
+            }
+        };
+
+        Ok(inst)
+    }
+}
+
+/// Assembles `source` into a ROM image that is loaded at `origin`.
+pub fn assemble_at(source: &str, origin: u16) -> Result<Vec<u8>, AsmError> {
+    let mut asm = Assembler::new(origin as usize);
+
+    for (i, line) in source.lines().enumerate() {
+        asm.parse_line(line, i + 1)?;
+    }
+
+    let mut rom = Vec::new();
+
+    for stmt in &asm.statements {
+        let bytes = asm.encode(stmt)?;
+        let offset = stmt.addr - asm.origin;
+
+        if rom.len() < offset + bytes.len() {
+            rom.resize(offset + bytes.len(), 0);
+        }
+
+        rom[offset..offset + bytes.len()].copy_from_slice(&bytes);
+    }
+
+    Ok(rom)
+}
+
+pub fn assemble(source: &str) -> Result<Vec<u8>, AsmError> {
+    assemble_at(source, 0x200)
+}
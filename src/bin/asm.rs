@@ -0,0 +1,37 @@
+extern crate chip8;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use chip8::asm;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() != 3 {
+        eprintln!("usage: {} <source> <output>", args[0]);
+        process::exit(2);
+    }
+
+    let source = match fs::read_to_string(&args[1]) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("failed to read {}: {}", args[1], err);
+            process::exit(1);
+        }
+    };
+
+    let rom = match asm::assemble(&source) {
+        Ok(rom) => rom,
+        Err(err) => {
+            eprintln!("{}:{}", args[1], err);
+            process::exit(1);
+        }
+    };
+
+    if let Err(err) = fs::write(&args[2], &rom) {
+        eprintln!("failed to write {}: {}", args[2], err);
+        process::exit(1);
+    }
+}
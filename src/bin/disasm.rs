@@ -0,0 +1,49 @@
+extern crate chip8;
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process;
+
+use chip8::disasm;
+
+fn parse_number(text: &str) -> Result<u16, std::num::ParseIntError> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => text.parse()
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!("usage: {} <rom> [origin]", args[0]);
+        process::exit(2);
+    }
+
+    let origin = match args.get(2) {
+        Some(origin) => match parse_number(origin) {
+            Ok(origin) => origin,
+            Err(_) => {
+                eprintln!("invalid origin address: {}", origin);
+                process::exit(2);
+            }
+        },
+        None => 0x200
+    };
+
+    let program = {
+        let mut buf = Vec::new();
+        let result = File::open(&args[1]).and_then(|mut file| file.read_to_end(&mut buf));
+
+        if let Err(err) = result {
+            eprintln!("failed to read {}: {}", args[1], err);
+            process::exit(1);
+        }
+
+        buf
+    };
+
+    print!("{}", disasm::disassemble(&program, origin));
+}
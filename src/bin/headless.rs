@@ -0,0 +1,206 @@
+extern crate chip8;
+
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
+use std::process;
+
+use chip8::cpu::CPU;
+use chip8::cpu::memory::{Memory, XOCHIP_MEMORY_SIZE};
+use chip8::cpu::quirks::Quirks;
+use chip8::headless::{self, Limit, Options};
+use chip8::io::NullAudio;
+use chip8::io::capture::{Capture, CaptureFormat};
+use chip8::io::palette::Palette;
+use chip8::io::screenshot;
+use chip8::scheduler::{MAX_INSTRUCTIONS_PER_FRAME, MIN_INSTRUCTIONS_PER_FRAME};
+
+const USAGE: &str = "usage: chip8_headless <rom> [options]
+
+options:
+    --frames <n>          run for n frames (default 60)
+    --instructions <n>    run for n instructions instead
+    --ipf <n>             instructions per frame (default 10)
+    --input <script>      apply keypad input from a script
+    --seed <n>            seed for RND
+    --quirks <profile>    vip, chip48, schip or modern (default)
+    --xochip              use a 64K address space
+    --text <file>         write the display as text, `-` for stdout
+    --png <file>          write the display as a PNG
+    --capture <file>      record every frame to a GIF, or raw RGB24 frames if
+                          the file doesn't end in .gif
+    --scale <n>           image pixels per lores pixel, also in hires (default 2)
+    --palette <palette>   image colours, a name or 2 or 4 hex colours
+    --json <file>         write the registers as JSON, `-` for stdout
+
+Without any output options, the display and registers are written to stdout.";
+
+const DEFAULT_SCALE: u32 = 2;
+const MAX_SCALE: u32 = 64;
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn parse_number(text: &str) -> u64 {
+    let result = match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse()
+    };
+
+    result.unwrap_or_else(|_| usage_error(&format!("invalid number: {}", text)))
+}
+
+fn parse_in_range(name: &str, text: &str, min: u64, max: u64) -> u64 {
+    let n = parse_number(text);
+
+    if !(min..=max).contains(&n) {
+        usage_error(&format!("{} must be between {} and {}", name, min, max));
+    }
+
+    n
+}
+
+fn read(path: &str) -> Vec<u8> {
+    fs::read(path).unwrap_or_else(|err| {
+        eprintln!("failed to read {}: {}", path, err);
+        process::exit(1);
+    })
+}
+
+fn write(path: &str, data: &[u8]) {
+    let result = match path {
+        "-" => io::stdout().write_all(data),
+        _ => fs::write(path, data)
+    };
+
+    if let Err(err) = result {
+        eprintln!("failed to write {}: {}", path, err);
+        process::exit(1);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let mut args = args.iter().map(String::as_str);
+
+    let mut rom = None;
+    let mut options = Options::default();
+    let mut seed = None;
+    let mut quirks = Quirks::modern();
+    let mut xochip = false;
+    let (mut text, mut png, mut json) = (None, None, None);
+    let mut capture = None;
+    let mut scale = DEFAULT_SCALE;
+    let mut palette = Palette::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage_error(&format!("missing value for {}", arg)));
+
+        match arg {
+            "--frames" => options.limit = Limit::Frames(parse_in_range("frames", value(), 0, u32::MAX as u64) as u32),
+            "--instructions" => options.limit = Limit::Instructions(parse_number(value())),
+            "--ipf" => options.instructions_per_frame = parse_in_range("instructions per frame", value(),
+                MIN_INSTRUCTIONS_PER_FRAME as u64, MAX_INSTRUCTIONS_PER_FRAME as u64) as u32,
+            "--input" => {
+                let path = value();
+
+                options.input = headless::parse_script(&String::from_utf8_lossy(&read(path)))
+                    .unwrap_or_else(|err| {
+                        eprintln!("{}:{}", path, err);
+                        process::exit(1);
+                    });
+            },
+            "--seed" => seed = Some(parse_number(value())),
+            "--quirks" => {
+                let name = value();
+                quirks = Quirks::from_name(name)
+                    .unwrap_or_else(|| usage_error(&format!("unknown quirks profile: {}", name)));
+            },
+            "--xochip" => xochip = true,
+            "--text" => text = Some(value()),
+            "--png" => png = Some(value()),
+            "--json" => json = Some(value()),
+            "--capture" => capture = Some(value()),
+            "--scale" => scale = parse_in_range("scale", value(), 1, MAX_SCALE as u64) as u32,
+            "--palette" => {
+                let text = value();
+                palette = Palette::parse(text).unwrap_or_else(|| usage_error(&format!("invalid palette: {}", text)));
+            },
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            },
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option: {}", arg)),
+            _ if rom.is_none() => rom = Some(arg),
+            _ => usage_error(&format!("unexpected argument: {}", arg))
+        }
+    }
+
+    let rom = rom.unwrap_or_else(|| usage_error("no ROM given"));
+    let program = read(rom);
+
+    let mut cpu = match seed {
+        Some(seed) => CPU::with_seed(quirks, seed),
+        None => CPU::with_quirks(quirks)
+    };
+
+    if xochip {
+        cpu.mem = Memory::with_size(XOCHIP_MEMORY_SIZE);
+    }
+
+    if cpu.mem.load_program(&program).is_none() {
+        eprintln!("{} is too large to fit in memory", rom);
+        process::exit(1);
+    }
+
+    let report = match capture {
+        Some(path) => {
+            let file = File::create(path).unwrap_or_else(|err| {
+                eprintln!("failed to create {}: {}", path, err);
+                process::exit(1);
+            });
+
+            let write_error = |err: io::Error| -> ! {
+                eprintln!("failed to write {}: {}", path, err);
+                process::exit(1);
+            };
+
+            let mut capture = Capture::new(BufWriter::new(file), CaptureFormat::from_path(path), palette, scale)
+                .unwrap_or_else(|err| write_error(err));
+            let report = headless::run_with(&mut cpu, &options, &mut capture, &mut NullAudio);
+            capture.finish().unwrap_or_else(|err| write_error(err));
+            report
+        },
+        None => headless::run(&mut cpu, &options)
+    };
+
+    if text.is_none() && png.is_none() && json.is_none() {
+        text = Some("-");
+        json = Some("-");
+    }
+
+    if let Some(path) = text {
+        write(path, screenshot::text(&cpu.env).as_bytes());
+    }
+
+    if let Some(path) = png {
+        write(path, &screenshot::image_png(&cpu.env, &palette, scale));
+    }
+
+    if let Some(path) = json {
+        write(path, headless::registers_json(&cpu, &report).as_bytes());
+    }
+
+    if report.blocked {
+        eprintln!("stopped after {} instructions waiting for a key, with no input left", report.instructions);
+    }
+
+    if let Some(err) = report.error {
+        eprintln!("CPU crashed: {}", err);
+        process::exit(1);
+    }
+}
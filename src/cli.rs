@@ -0,0 +1,200 @@
+use chip8::cpu::quirks::Quirks;
+use chip8::io::palette::{Palette, PALETTE_NAMES};
+use chip8::io::phosphor::PhosphorMode;
+use chip8::io::sound::{Tone, Waveform};
+use chip8::scheduler::{DEFAULT_INSTRUCTIONS_PER_FRAME, MAX_INSTRUCTIONS_PER_FRAME, MIN_INSTRUCTIONS_PER_FRAME};
+
+pub const USAGE: &str = "usage: chip8_emu <rom> [options]
+
+options:
+    --quirks <profile>       vip, chip48, schip or modern
+    --xochip                 use a 64K address space
+    --ipf <n>                CPU speed in instructions per frame (default 10)
+    --scale <n>              window, screenshot and capture pixels per lores
+                             pixel (default 16)
+    --palette <palette>      default, green, amber, octo, gameboy, or 2 or 4
+                             comma-separated hex colours, e.g. 000000,33FF66
+    --fullscreen             start in fullscreen, F11 toggles
+    --filter <mode>          flicker filter: off (default), blend or fade
+    --keymap <file>          key bindings (default keymap.ini, if it exists)
+    --tone <hz>              buzzer frequency (default 440)
+    --waveform <wave>        buzzer waveform: square (default), sine or triangle
+    --volume <percent>       buzzer volume from 0 to 100 (default 25)
+    --load-address <addr>    where to load the ROM (default 0x200)
+    --seed <n>               seed for RND
+    --paused                 start paused, P resumes
+    --debug                  start in the debugger
+    --headless               run without a window and print the final state
+    --tui                    run in the terminal, with the registers alongside;
+                             keys are held until they stop repeating, Ctrl+C quits
+    --frames <n>             frames to run with --headless (default 60)
+    --capture <file>         record the display to a GIF, or raw RGB24 frames
+                             if the file doesn't end in .gif; F10 toggles
+    --record <movie>         record keypad input to a movie
+    --play <movie>           play keypad input back from a movie";
+
+pub const DEFAULT_SCALE: u32 = 16;
+pub const MAX_SCALE: u32 = 64;
+
+pub const DEFAULT_LOAD_ADDRESS: u16 = 0x200;
+
+pub const MIN_TONE_FREQUENCY: u64 = 20;
+pub const MAX_TONE_FREQUENCY: u64 = 20000;
+
+/// Keymap loaded when none is given, if it exists.
+pub const DEFAULT_KEYMAP: &str = "keymap.ini";
+
+pub struct Options {
+    pub rom: String,
+    pub quirks: Quirks,
+    pub xochip: bool,
+    pub instructions_per_frame: u32,
+    pub scale: u32,
+    pub palette: Palette,
+    pub fullscreen: bool,
+    pub filter: PhosphorMode,
+    pub keymap: Option<String>,
+    pub tone: Tone,
+    pub load_address: u16,
+    pub seed: Option<u64>,
+    pub paused: bool,
+    pub debug: bool,
+    pub headless: bool,
+    pub tui: bool,
+    /// Frames to run for in headless mode, if not the default.
+    pub frames: Option<u32>,
+    pub capture: Option<String>,
+    pub record: Option<String>,
+    pub play: Option<String>
+}
+
+pub enum Command {
+    Run(Options),
+    Help
+}
+
+fn parse_number(text: &str) -> Result<u64, String> {
+    let result = match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => text.parse()
+    };
+
+    result.map_err(|_| format!("invalid number: {}", text))
+}
+
+fn parse_in_range(name: &str, text: &str, min: u64, max: u64) -> Result<u64, String> {
+    let n = parse_number(text)?;
+
+    if !(min..=max).contains(&n) {
+        return Err(format!("{} must be between {} and {}", name, min, max));
+    }
+
+    Ok(n)
+}
+
+/// Parses the emulator's arguments, without the program name.
+pub fn parse<'a>(args: impl IntoIterator<Item = &'a str>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+
+    let mut rom = None;
+    let mut given = Vec::new();
+    let mut options = Options {
+        rom: String::new(),
+        quirks: Quirks::default(),
+        xochip: false,
+        instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+        scale: DEFAULT_SCALE,
+        palette: Palette::default(),
+        fullscreen: false,
+        filter: PhosphorMode::Off,
+        keymap: None,
+        tone: Tone::default(),
+        load_address: DEFAULT_LOAD_ADDRESS,
+        seed: None,
+        paused: false,
+        debug: false,
+        headless: false,
+        tui: false,
+        frames: None,
+        capture: None,
+        record: None,
+        play: None
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("missing value for {}", arg));
+
+        if arg.starts_with("--") {
+            given.push(arg);
+        }
+
+        match arg {
+            "--quirks" => {
+                let name = value()?;
+                options.quirks = Quirks::from_name(name).ok_or_else(|| format!("unknown quirks profile: {}", name))?;
+            },
+            "--xochip" => options.xochip = true,
+            "--ipf" => options.instructions_per_frame = parse_in_range("instructions per frame", value()?,
+                MIN_INSTRUCTIONS_PER_FRAME as u64, MAX_INSTRUCTIONS_PER_FRAME as u64)? as u32,
+            "--scale" => options.scale = parse_in_range("scale", value()?, 1, MAX_SCALE as u64)? as u32,
+            "--palette" => {
+                let name = value()?;
+                options.palette = Palette::parse(name).ok_or_else(|| {
+                    format!("invalid palette: {} (expected one of {}, or 2 or 4 hex colours)", name, PALETTE_NAMES.join(", "))
+                })?;
+            },
+            "--fullscreen" => options.fullscreen = true,
+            "--filter" => {
+                let name = value()?;
+                options.filter = PhosphorMode::from_name(name).ok_or_else(|| format!("unknown filter: {}", name))?;
+            },
+            "--keymap" => options.keymap = Some(value()?.to_string()),
+            "--tone" => options.tone.frequency = parse_in_range("tone", value()?,
+                MIN_TONE_FREQUENCY, MAX_TONE_FREQUENCY)? as f32,
+            "--waveform" => {
+                let name = value()?;
+                options.tone.waveform = Waveform::from_name(name).ok_or_else(|| format!("unknown waveform: {}", name))?;
+            },
+            "--volume" => options.tone.volume = parse_in_range("volume", value()?, 0, 100)? as f32 / 100.0,
+            "--load-address" => options.load_address = parse_in_range("load address", value()?, 0, 0xFFFF)? as u16,
+            "--seed" => options.seed = Some(parse_number(value()?)?),
+            "--paused" => options.paused = true,
+            "--debug" => options.debug = true,
+            "--headless" => options.headless = true,
+            "--tui" => options.tui = true,
+            "--frames" => options.frames = Some(parse_in_range("frames", value()?, 0, u32::MAX as u64)? as u32),
+            "--capture" => options.capture = Some(value()?.to_string()),
+            "--record" => options.record = Some(value()?.to_string()),
+            "--play" => options.play = Some(value()?.to_string()),
+            "-h" | "--help" => return Ok(Command::Help),
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ if rom.is_none() => rom = Some(arg),
+            _ => return Err(format!("unexpected argument: {}", arg))
+        }
+    }
+
+    if options.record.is_some() && options.play.is_some() {
+        return Err("--record and --play can't be used together".to_string());
+    }
+
+    if options.headless {
+        let ignored = ["--record", "--keymap", "--paused", "--debug", "--fullscreen", "--filter", "--tone",
+            "--waveform", "--volume"];
+
+        if let Some(name) = ignored.iter().find(|name| given.contains(name)) {
+            return Err(format!("{} can't be used with --headless", name));
+        }
+    }
+
+    if options.headless && options.tui {
+        return Err("--headless and --tui can't be used together".to_string());
+    }
+
+    if options.frames.is_some() && !options.headless {
+        return Err("--frames requires --headless".to_string());
+    }
+
+    options.rom = rom.ok_or_else(|| "no ROM given".to_string())?.to_string();
+
+    Ok(Command::Run(options))
+}
@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccess {
+    Fetch,
+    Read,
+    Write
+}
+
+impl fmt::Display for MemoryAccess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MemoryAccess::Fetch => write!(f, "fetch"),
+            MemoryAccess::Read => write!(f, "read"),
+            MemoryAccess::Write => write!(f, "write")
+        }
+    }
+}
+
+/// The opcode does not correspond to any known instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    pub opcode: u16
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown instruction {:04X}", self.opcode)
+    }
+}
+
+impl Error for DecodeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CPUError {
+    /// The opcode at `addr` is not a known instruction.
+    UnknownInstruction { opcode: u16, addr: u16 },
+    /// RET was executed at `addr` with an empty call stack.
+    StackUnderflow { addr: u16 },
+    /// CALL was executed at `addr` with a full call stack.
+    StackOverflow { addr: u16 },
+    /// The instruction at `pc` accessed memory outside of the address space.
+    MemoryFault { addr: usize, access: MemoryAccess, pc: u16 }
+}
+
+impl fmt::Display for CPUError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CPUError::UnknownInstruction { opcode, addr } =>
+                write!(f, "unknown instruction {:04X} at {:04X}", opcode, addr),
+            CPUError::StackUnderflow { addr } =>
+                write!(f, "can't RET with an empty stack at {:04X}", addr),
+            CPUError::StackOverflow { addr } =>
+                write!(f, "can't CALL with a full stack at {:04X}", addr),
+            CPUError::MemoryFault { addr, access, pc } =>
+                write!(f, "memory fault: {} of {:04X} at {:04X}", access, addr, pc)
+        }
+    }
+}
+
+impl Error for CPUError {}
+
+/// A save state could not be restored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StateError {
+    /// The data doesn't start with the save state magic bytes.
+    BadMagic,
+    /// The save state was written by an incompatible version of the format.
+    UnsupportedVersion(u8),
+    /// The data ended before the state was complete.
+    Truncated,
+    /// A field holds a value the CPU can't be in.
+    Invalid(&'static str)
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::BadMagic => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => write!(f, "unsupported save state version {}", version),
+            StateError::Truncated => write!(f, "save state is truncated"),
+            StateError::Invalid(field) => write!(f, "save state has an invalid {}", field)
+        }
+    }
+}
+
+impl Error for StateError {}
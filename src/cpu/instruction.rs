@@ -0,0 +1,274 @@
+use std::fmt;
+
+use super::error::DecodeError;
+
+/// A decoded CHIP-8, SUPER-CHIP or XO-CHIP instruction. `x` and `y` are
+/// register indices, `byte`, `addr` and `n` are immediate operands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Sys { addr: u16 },
+    Cls,
+    Ret,
+    ScrollDown { n: u8 },
+    ScrollUp { n: u8 },
+    ScrollRight,
+    ScrollLeft,
+    Exit,
+    Low,
+    High,
+    Jp { addr: u16 },
+    Call { addr: u16 },
+    Se { x: u8, byte: u8 },
+    Sne { x: u8, byte: u8 },
+    SeReg { x: u8, y: u8 },
+    StoreRange { x: u8, y: u8 },
+    LoadRange { x: u8, y: u8 },
+    Ld { x: u8, byte: u8 },
+    Add { x: u8, byte: u8 },
+    LdReg { x: u8, y: u8 },
+    Or { x: u8, y: u8 },
+    And { x: u8, y: u8 },
+    Xor { x: u8, y: u8 },
+    AddReg { x: u8, y: u8 },
+    Sub { x: u8, y: u8 },
+    Shr { x: u8, y: u8 },
+    Subn { x: u8, y: u8 },
+    Shl { x: u8, y: u8 },
+    SneReg { x: u8, y: u8 },
+    LdI { addr: u16 },
+    /// `x` is only used with the jump quirk, which adds Vx instead of V0.
+    JpV0 { x: u8, addr: u16 },
+    Rnd { x: u8, byte: u8 },
+    Drw { x: u8, y: u8, n: u8 },
+    Skp { x: u8 },
+    Sknp { x: u8 },
+    /// Loads I with the 16-bit word following the instruction.
+    LdILong,
+    Plane { n: u8 },
+    LdAudio,
+    LdVxDt { x: u8 },
+    LdVxK { x: u8 },
+    LdDt { x: u8 },
+    LdSt { x: u8 },
+    AddI { x: u8 },
+    LdF { x: u8 },
+    LdHf { x: u8 },
+    LdB { x: u8 },
+    LdPitch { x: u8 },
+    StoreRegs { x: u8 },
+    LoadRegs { x: u8 },
+    StoreFlags { x: u8 },
+    LoadFlags { x: u8 }
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Result<Instruction, DecodeError> {
+        let op = ((opcode & 0xF000) >> 12) as u8;
+        let x = ((opcode & 0x0F00) >> 8) as u8;
+        let y = ((opcode & 0x00F0) >> 4) as u8;
+        let n = (opcode & 0x000F) as u8;
+        let byte = (opcode & 0x00FF) as u8;
+        let addr = opcode & 0x0FFF;
+
+        let inst = match op {
+            0x0 => match addr {
+                0x0E0 => Instruction::Cls,
+                0x0EE => Instruction::Ret,
+                0x0C0..=0x0CF => Instruction::ScrollDown { n },
+                0x0D0..=0x0DF => Instruction::ScrollUp { n },
+                0x0FB => Instruction::ScrollRight,
+                0x0FC => Instruction::ScrollLeft,
+                0x0FD => Instruction::Exit,
+                0x0FE => Instruction::Low,
+                0x0FF => Instruction::High,
+                _ => Instruction::Sys { addr }
+            },
+            0x1 => Instruction::Jp { addr },
+            0x2 => Instruction::Call { addr },
+            0x3 => Instruction::Se { x, byte },
+            0x4 => Instruction::Sne { x, byte },
+            0x5 => match n {
+                0x0 => Instruction::SeReg { x, y },
+                0x2 => Instruction::StoreRange { x, y },
+                0x3 => Instruction::LoadRange { x, y },
+                _ => return Err(DecodeError { opcode })
+            },
+            0x6 => Instruction::Ld { x, byte },
+            0x7 => Instruction::Add { x, byte },
+            0x8 => match n {
+                0x0 => Instruction::LdReg { x, y },
+                0x1 => Instruction::Or { x, y },
+                0x2 => Instruction::And { x, y },
+                0x3 => Instruction::Xor { x, y },
+                0x4 => Instruction::AddReg { x, y },
+                0x5 => Instruction::Sub { x, y },
+                0x6 => Instruction::Shr { x, y },
+                0x7 => Instruction::Subn { x, y },
+                0xE => Instruction::Shl { x, y },
+                _ => return Err(DecodeError { opcode })
+            },
+            0x9 if n == 0x0 => Instruction::SneReg { x, y },
+            0xA => Instruction::LdI { addr },
+            0xB => Instruction::JpV0 { x, addr },
+            0xC => Instruction::Rnd { x, byte },
+            0xD => Instruction::Drw { x, y, n },
+            0xE => match byte {
+                0x9E => Instruction::Skp { x },
+                0xA1 => Instruction::Sknp { x },
+                _ => return Err(DecodeError { opcode })
+            },
+            0xF => match byte {
+                0x00 if x == 0x0 => Instruction::LdILong,
+                0x01 => Instruction::Plane { n: x },
+                0x02 if x == 0x0 => Instruction::LdAudio,
+                0x07 => Instruction::LdVxDt { x },
+                0x0A => Instruction::LdVxK { x },
+                0x15 => Instruction::LdDt { x },
+                0x18 => Instruction::LdSt { x },
+                0x1E => Instruction::AddI { x },
+                0x29 => Instruction::LdF { x },
+                0x30 => Instruction::LdHf { x },
+                0x33 => Instruction::LdB { x },
+                0x3A => Instruction::LdPitch { x },
+                0x55 => Instruction::StoreRegs { x },
+                0x65 => Instruction::LoadRegs { x },
+                0x75 => Instruction::StoreFlags { x },
+                0x85 => Instruction::LoadFlags { x },
+                _ => return Err(DecodeError { opcode })
+            },
+            _ => return Err(DecodeError { opcode })
+        };
+
+        Ok(inst)
+    }
+
+    pub fn encode(&self) -> u16 {
+        fn xy(op: u16, x: u8, y: u8, n: u16) -> u16 {
+            op << 12 | (x as u16 & 0xF) << 8 | (y as u16 & 0xF) << 4 | n
+        }
+
+        fn xb(op: u16, x: u8, byte: u16) -> u16 {
+            op << 12 | (x as u16 & 0xF) << 8 | byte
+        }
+
+        match *self {
+            Instruction::Sys { addr } => addr & 0xFFF,
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::ScrollDown { n } => 0x00C0 | (n as u16 & 0xF),
+            Instruction::ScrollUp { n } => 0x00D0 | (n as u16 & 0xF),
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::Low => 0x00FE,
+            Instruction::High => 0x00FF,
+            Instruction::Jp { addr } => 0x1000 | (addr & 0xFFF),
+            Instruction::Call { addr } => 0x2000 | (addr & 0xFFF),
+            Instruction::Se { x, byte } => xb(0x3, x, byte as u16),
+            Instruction::Sne { x, byte } => xb(0x4, x, byte as u16),
+            Instruction::SeReg { x, y } => xy(0x5, x, y, 0x0),
+            Instruction::StoreRange { x, y } => xy(0x5, x, y, 0x2),
+            Instruction::LoadRange { x, y } => xy(0x5, x, y, 0x3),
+            Instruction::Ld { x, byte } => xb(0x6, x, byte as u16),
+            Instruction::Add { x, byte } => xb(0x7, x, byte as u16),
+            Instruction::LdReg { x, y } => xy(0x8, x, y, 0x0),
+            Instruction::Or { x, y } => xy(0x8, x, y, 0x1),
+            Instruction::And { x, y } => xy(0x8, x, y, 0x2),
+            Instruction::Xor { x, y } => xy(0x8, x, y, 0x3),
+            Instruction::AddReg { x, y } => xy(0x8, x, y, 0x4),
+            Instruction::Sub { x, y } => xy(0x8, x, y, 0x5),
+            Instruction::Shr { x, y } => xy(0x8, x, y, 0x6),
+            Instruction::Subn { x, y } => xy(0x8, x, y, 0x7),
+            Instruction::Shl { x, y } => xy(0x8, x, y, 0xE),
+            Instruction::SneReg { x, y } => xy(0x9, x, y, 0x0),
+            Instruction::LdI { addr } => 0xA000 | (addr & 0xFFF),
+            Instruction::JpV0 { addr, .. } => 0xB000 | (addr & 0xFFF),
+            Instruction::Rnd { x, byte } => xb(0xC, x, byte as u16),
+            Instruction::Drw { x, y, n } => xy(0xD, x, y, n as u16 & 0xF),
+            Instruction::Skp { x } => xb(0xE, x, 0x9E),
+            Instruction::Sknp { x } => xb(0xE, x, 0xA1),
+            Instruction::LdILong => 0xF000,
+            Instruction::Plane { n } => xb(0xF, n, 0x01),
+            Instruction::LdAudio => 0xF002,
+            Instruction::LdVxDt { x } => xb(0xF, x, 0x07),
+            Instruction::LdVxK { x } => xb(0xF, x, 0x0A),
+            Instruction::LdDt { x } => xb(0xF, x, 0x15),
+            Instruction::LdSt { x } => xb(0xF, x, 0x18),
+            Instruction::AddI { x } => xb(0xF, x, 0x1E),
+            Instruction::LdF { x } => xb(0xF, x, 0x29),
+            Instruction::LdHf { x } => xb(0xF, x, 0x30),
+            Instruction::LdB { x } => xb(0xF, x, 0x33),
+            Instruction::LdPitch { x } => xb(0xF, x, 0x3A),
+            Instruction::StoreRegs { x } => xb(0xF, x, 0x55),
+            Instruction::LoadRegs { x } => xb(0xF, x, 0x65),
+            Instruction::StoreFlags { x } => xb(0xF, x, 0x75),
+            Instruction::LoadFlags { x } => xb(0xF, x, 0x85)
+        }
+    }
+
+    /// Size of the instruction in bytes, including any trailing operand word.
+    pub fn size(&self) -> u16 {
+        match self {
+            Instruction::LdILong => 4,
+            _ => 2
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Sys { addr } => write!(f, "SYS 0x{:03X}", addr),
+            Instruction::Cls => write!(f, "CLS"),
+            Instruction::Ret => write!(f, "RET"),
+            Instruction::ScrollDown { n } => write!(f, "SCD 0x{:X}", n),
+            Instruction::ScrollUp { n } => write!(f, "SCU 0x{:X}", n),
+            Instruction::ScrollRight => write!(f, "SCR"),
+            Instruction::ScrollLeft => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::Low => write!(f, "LOW"),
+            Instruction::High => write!(f, "HIGH"),
+            Instruction::Jp { addr } => write!(f, "JP 0x{:03X}", addr),
+            Instruction::Call { addr } => write!(f, "CALL 0x{:03X}", addr),
+            Instruction::Se { x, byte } => write!(f, "SE V{:X}, 0x{:02X}", x, byte),
+            Instruction::Sne { x, byte } => write!(f, "SNE V{:X}, 0x{:02X}", x, byte),
+            Instruction::SeReg { x, y } => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::StoreRange { x, y } => write!(f, "LD [I], V{:X}-V{:X}", x, y),
+            Instruction::LoadRange { x, y } => write!(f, "LD V{:X}-V{:X}, [I]", x, y),
+            Instruction::Ld { x, byte } => write!(f, "LD V{:X}, 0x{:02X}", x, byte),
+            Instruction::Add { x, byte } => write!(f, "ADD V{:X}, 0x{:02X}", x, byte),
+            Instruction::LdReg { x, y } => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or { x, y } => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And { x, y } => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor { x, y } => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddReg { x, y } => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub { x, y } => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::Shr { x, y } => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::Subn { x, y } => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::Shl { x, y } => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SneReg { x, y } => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LdI { addr } => write!(f, "LD I, 0x{:03X}", addr),
+            Instruction::JpV0 { addr, .. } => write!(f, "JP V0, 0x{:03X}", addr),
+            Instruction::Rnd { x, byte } => write!(f, "RND V{:X}, 0x{:02X}", x, byte),
+            Instruction::Drw { x, y, n } => write!(f, "DRW V{:X}, V{:X}, 0x{:X}", x, y, n),
+            Instruction::Skp { x } => write!(f, "SKP V{:X}", x),
+            Instruction::Sknp { x } => write!(f, "SKNP V{:X}", x),
+            Instruction::LdILong => write!(f, "LD I, LONG"),
+            Instruction::Plane { n } => write!(f, "PLANE 0x{:X}", n),
+            Instruction::LdAudio => write!(f, "LD AUDIO, [I]"),
+            Instruction::LdVxDt { x } => write!(f, "LD V{:X}, DT", x),
+            Instruction::LdVxK { x } => write!(f, "LD V{:X}, K", x),
+            Instruction::LdDt { x } => write!(f, "LD DT, V{:X}", x),
+            Instruction::LdSt { x } => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddI { x } => write!(f, "ADD I, V{:X}", x),
+            Instruction::LdF { x } => write!(f, "LD F, V{:X}", x),
+            Instruction::LdHf { x } => write!(f, "LD HF, V{:X}", x),
+            Instruction::LdB { x } => write!(f, "LD B, V{:X}", x),
+            Instruction::LdPitch { x } => write!(f, "LD PITCH, V{:X}", x),
+            Instruction::StoreRegs { x } => write!(f, "LD [I], V{:X}", x),
+            Instruction::LoadRegs { x } => write!(f, "LD V{:X}, [I]", x),
+            Instruction::StoreFlags { x } => write!(f, "LD R, V{:X}", x),
+            Instruction::LoadFlags { x } => write!(f, "LD V{:X}, R", x)
+        }
+    }
+}
@@ -1,24 +1,42 @@
-use super::super::io::chars::CHIP8_CHARACTERS;
+use super::super::io::chars::{CHIP8_CHARACTERS, SCHIP8_CHARACTERS};
 
 pub struct Memory {
-    mem: [u8; 4096]
+    mem: Vec<u8>
 }
 
 pub const CHIP8_MEMORY_SIZE: usize = 4096;
+pub const XOCHIP_MEMORY_SIZE: usize = 65536;
+pub const CHIP8_CHARACTERS_ADDR: usize = 0x0;
+pub const SCHIP8_CHARACTERS_ADDR: usize = 0x50;
+
+impl Default for Memory {
+    fn default() -> Memory {
+        Memory::new()
+    }
+}
 
 impl Memory {
     pub fn new() -> Memory {
-        let mut mem = Memory { mem: [0; CHIP8_MEMORY_SIZE] };
-        mem.load(0x0, &CHIP8_CHARACTERS);
+        Memory::with_size(CHIP8_MEMORY_SIZE)
+    }
+
+    pub fn with_size(size: usize) -> Memory {
+        let mut mem = Memory { mem: vec![0; size] };
+        mem.load(CHIP8_CHARACTERS_ADDR, &CHIP8_CHARACTERS);
+        mem.load(SCHIP8_CHARACTERS_ADDR, &SCHIP8_CHARACTERS);
         mem
     }
 
+    pub fn size(&self) -> usize {
+        self.mem.len()
+    }
+
     pub fn in_range(&self, addr: usize) -> bool {
         addr < self.mem.len()
     }
 
     pub fn block_in_range(&self, addr: usize, size: usize) -> bool {
-        addr < self.mem.len() && addr + size < self.mem.len()
+        addr < self.mem.len() && addr + size <= self.mem.len()
     }
 
     pub fn block(&self, addr: usize, size: usize) -> Option<&[u8]> {
@@ -1,35 +1,62 @@
 pub mod registers;
 pub mod memory;
+pub mod error;
+pub mod quirks;
+pub mod instruction;
+pub mod rng;
+pub mod state;
 
 use self::registers::Registers;
-use self::memory::Memory;
+use self::memory::{Memory, CHIP8_CHARACTERS_ADDR, SCHIP8_CHARACTERS_ADDR, XOCHIP_MEMORY_SIZE};
+use self::error::{CPUError, MemoryAccess};
+use self::quirks::Quirks;
+use self::instruction::Instruction;
+use self::rng::{RandomSource, XorShift};
 
 use std;
 use std::num::Wrapping;
 
-use rand;
-use rand::prelude::*;
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Interrupt {
     None,
-    AwaitKey(u8)
+    AwaitKey(u8),
+    AwaitVBlank,
+    Exit
 }
 
+pub const LORES_WIDTH: u8 = 64;
+pub const LORES_HEIGHT: u8 = 32;
+pub const HIRES_WIDTH: u8 = 128;
+pub const HIRES_HEIGHT: u8 = 64;
+
+pub const DISPLAY_PLANES: u8 = 2;
+
 pub struct CPUEnvironment {
     pub keyboard: [bool; 16],
-    pub display: Vec<bool>,
+    /// One entry per pixel, holding a bitmask of the planes the pixel is lit on.
+    pub display: Vec<u8>,
     pub display_width: u8,
-    pub display_height: u8
+    pub display_height: u8,
+    /// Bitmask of the planes that drawing, clearing and scrolling affect.
+    pub planes: u8,
+    pub audio_pattern: [u8; 16],
+    pub audio_pitch: u8,
+    /// Changes whenever `display` does, so that frontends can skip redrawing
+    /// an unchanged display.
+    pub display_version: u64
 }
 
 impl CPUEnvironment {
     pub fn new(display_width: u8, display_height: u8) -> CPUEnvironment {
         CPUEnvironment {
             keyboard: [false; 16],
-            display: vec![false; display_width as usize * display_height as usize],
+            display: vec![0; display_width as usize * display_height as usize],
             display_width,
-            display_height
+            display_height,
+            planes: 0x1,
+            audio_pattern: [0; 16],
+            audio_pitch: 64,
+            display_version: 0
         }
     }
 
@@ -40,28 +67,121 @@ impl CPUEnvironment {
         }
     }
 
-    pub fn set_pixel(&mut self, x: u32, y: u32, white: bool) {
+    fn pixel_index(&self, x: u32, y: u32) -> usize {
         let x = x % self.display_width as u32;
         let y = y % self.display_height as u32;
+        (y * self.display_width as u32 + x) as usize
+    }
+
+    /// Records that `display` was changed.
+    pub fn mark_display_changed(&mut self) {
+        self.display_version = self.display_version.wrapping_add(1);
+    }
+
+    pub fn set_plane_pixel(&mut self, plane: u8, x: u32, y: u32, white: bool) {
+        let i = self.pixel_index(x, y);
+
+        if let Some(pix) = self.display.get_mut(i) {
+            let old = *pix;
 
-        match self.display.get_mut((y * self.display_width as u32 + x) as usize) {
-            Some(pix) => *pix = white,
-            None => ()
+            if white {
+                *pix |= 1 << plane;
+            } else {
+                *pix &= !(1 << plane);
+            }
+
+            if *pix != old {
+                self.mark_display_changed();
+            }
         }
     }
 
-    pub fn pixel(&mut self, x: u32, y: u32) -> bool {
-        let x = x % self.display_width as u32;
-        let y = y % self.display_height as u32;
+    pub fn plane_pixel(&self, plane: u8, x: u32, y: u32) -> bool {
+        match self.display.get(self.pixel_index(x, y)) {
+            Some(pix) => *pix & (1 << plane) != 0,
+            None => false
+        }
+    }
 
-        match self.display.get((y * self.display_width as u32 + x) as usize) {
+    pub fn set_pixel(&mut self, x: u32, y: u32, white: bool) {
+        self.set_plane_pixel(0, x, y, white);
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        self.plane_pixel(0, x, y)
+    }
+
+    /// Returns the colour index of a pixel, i.e. the bitmask of planes it is lit on.
+    pub fn colour(&self, x: u32, y: u32) -> u8 {
+        match self.display.get(self.pixel_index(x, y)) {
             Some(pix) => *pix,
-            None => false
+            None => 0
         }
     }
 
     pub fn clear_screen(&mut self) {
-        self.display = vec![false; self.display_width as usize * self.display_height as usize];
+        let len = self.display_width as usize * self.display_height as usize;
+        let planes = self.planes;
+
+        self.display.resize(len, 0);
+        self.display.iter_mut().for_each(|pix| *pix &= !planes);
+        self.mark_display_changed();
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.display_width == HIRES_WIDTH && self.display_height == HIRES_HEIGHT
+    }
+
+    /// Switches between the 64x32 and 128x64 display modes, clearing the screen.
+    pub fn set_hires(&mut self, hires: bool) {
+        if hires {
+            self.display_width = HIRES_WIDTH;
+            self.display_height = HIRES_HEIGHT;
+        } else {
+            self.display_width = LORES_WIDTH;
+            self.display_height = LORES_HEIGHT;
+        }
+
+        self.display = vec![0; self.display_width as usize * self.display_height as usize];
+        self.mark_display_changed();
+    }
+
+    /// Shifts the selected planes by `dx` and `dy` pixels, filling vacated
+    /// pixels with black.
+    pub fn scroll(&mut self, dx: i32, dy: i32) {
+        let w = self.display_width as i32;
+        let h = self.display_height as i32;
+        let planes = self.planes;
+        let mut display: Vec<u8> = self.display.iter().map(|pix| *pix & !planes).collect();
+
+        for y in 0..h {
+            for x in 0..w {
+                let (sx, sy) = (x - dx, y - dy);
+
+                if sx >= 0 && sx < w && sy >= 0 && sy < h {
+                    display[(y * w + x) as usize] |= self.display[(sy * w + sx) as usize] & planes;
+                }
+            }
+        }
+
+        self.display = display;
+        self.mark_display_changed();
+    }
+
+    /// Frequency in Hz at which the audio pattern buffer is played back.
+    pub fn audio_frequency(&self) -> f32 {
+        4000.0 * 2f32.powf((self.audio_pitch as f32 - 64.0) / 48.0)
+    }
+}
+
+pub const STACK_SIZE: usize = 16;
+
+/// Registers Vx to Vy inclusive, in descending order if x > y.
+fn register_range(x: u8, y: u8) -> Box<dyn Iterator<Item = u8>> {
+    if x <= y {
+        Box::new(x..=y)
+    } else {
+        Box::new((y..=x).rev())
     }
 }
 
@@ -70,48 +190,110 @@ pub struct CPU {
     pub mem: Memory,
     pub env: CPUEnvironment,
     pub interrupt: Interrupt,
+    pub quirks: Quirks,
+    pub rpl_flags: [u8; 16],
     stack: Vec<u16>,
-    rng: ThreadRng,
+    rng: Box<dyn RandomSource>
 }
 
-fn unknown_inst() {
-    panic!("unknown instruction!")
+impl Default for CPU {
+    fn default() -> CPU {
+        CPU::new()
+    }
 }
 
 impl CPU {
     pub fn new() -> CPU {
+        CPU::with_quirks(Quirks::default())
+    }
+
+    /// Creates a CPU whose random numbers are seeded from the system's entropy.
+    pub fn with_quirks(quirks: Quirks) -> CPU {
+        CPU::with_rng(quirks, Box::new(XorShift::from_entropy()))
+    }
+
+    /// Creates a CPU whose `RND` results are reproducible from `seed`.
+    pub fn with_seed(quirks: Quirks, seed: u64) -> CPU {
+        CPU::with_rng(quirks, Box::new(XorShift::new(seed)))
+    }
+
+    pub fn with_rng(quirks: Quirks, rng: Box<dyn RandomSource>) -> CPU {
         CPU {
             regs: Registers::new(),
             mem: Memory::new(),
-            stack: Vec::with_capacity(16),
-            rng: rand::thread_rng(),
+            stack: Vec::with_capacity(STACK_SIZE),
+            rng,
             interrupt: Interrupt::None,
-            env: CPUEnvironment::new(64, 32)
+            quirks,
+            rpl_flags: [0; 16],
+            env: CPUEnvironment::new(LORES_WIDTH, LORES_HEIGHT)
         }
     }
 
+    /// Creates a CPU for XO-CHIP programs, with a 64K address space.
+    pub fn xochip() -> CPU {
+        let mut cpu = CPU::with_quirks(Quirks::modern());
+        cpu.mem = Memory::with_size(XOCHIP_MEMORY_SIZE);
+        cpu
+    }
+
+    /// The seed of the random number source.
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    /// Restarts the random number sequence from `seed`.
+    pub fn reseed(&mut self, seed: u64) {
+        self.rng.reseed(seed);
+    }
+
+    /// Return addresses of the active subroutine calls, innermost last.
+    pub fn stack(&self) -> &[u16] {
+        &self.stack
+    }
+
     fn jump(&mut self, addr: u16) {
-        self.regs.pc = addr - 2;
+        self.regs.pc = addr.wrapping_sub(2);
     }
 
+    /// Skips the next instruction, including both words of a long `LD I, x`.
     fn skip(&mut self) {
-        self.regs.pc += 2;
+        let next = self.regs.pc.wrapping_add(2);
+
+        if self.word(next) == Some(0xF000) {
+            self.regs.pc = self.regs.pc.wrapping_add(4);
+        } else {
+            self.regs.pc = next;
+        }
     }
 
-    fn call(&mut self, addr: u16) {
-        self.stack.push(self.regs.pc + 2);
+    fn call(&mut self, addr: u16) -> Result<(), CPUError> {
+        if self.stack.len() >= STACK_SIZE {
+            return Err(CPUError::StackOverflow { addr: self.regs.pc });
+        }
+
+        self.stack.push(self.regs.pc.wrapping_add(2));
         self.jump(addr);
+        Ok(())
     }
 
-    fn ret(&mut self) {
-
+    fn ret(&mut self) -> Result<(), CPUError> {
         if let Some(addr) = self.stack.pop() {
             self.jump(addr);
+            Ok(())
         } else {
-            panic!("can't RET with an empty stack");
+            Err(CPUError::StackUnderflow { addr: self.regs.pc })
         }
     }
 
+    fn fault(&self, addr: usize, access: MemoryAccess) -> CPUError {
+        CPUError::MemoryFault { addr, access, pc: self.regs.pc }
+    }
+
+    fn unknown_inst(&self, opcode: u16) -> CPUError {
+        CPUError::UnknownInstruction { opcode, addr: self.regs.pc }
+    }
+
     fn v(&self, i: u8) -> u8 {
         self.regs.v(i as usize).expect("invalid V register")
     }
@@ -120,34 +302,79 @@ impl CPU {
         self.regs.set_v(i as usize, v).expect("invalid V register")
     }
 
-    fn draw(&mut self, gx: u8, gy: u8, addr: u16, size: u8) {
-        let pixels = {
-            if let Some(block) = self.mem.block(addr as usize, size as usize) {
-                let mut vec = vec![0; size as usize];
-                vec.clone_from_slice(block);
-                vec
-            } else {
-                panic!("could not draw due to I being out of range")
-            }
+    fn set_v_logic(&mut self, i: u8, v: u8) {
+        self.set_v(i, v);
+
+        if self.quirks.vf_reset {
+            self.set_v(0xF, 0);
+        }
+    }
+
+    /// Draws an 8xN sprite, or a 16x16 sprite if `size` is 0. In hires mode
+    /// VF is set to the number of rows that collided, as on SUPER-CHIP.
+    /// When several planes are selected, the sprite data for each plane
+    /// follows the previous one in memory.
+    fn draw(&mut self, gx: u8, gy: u8, addr: u16, size: u8) -> Result<(), CPUError> {
+        let (width, height) = match size {
+            0 if self.quirks.lores_dxy0 && !self.env.is_hires() => (8, 16),
+            0 => (16, 16),
+            size => (8, size as usize)
         };
+        let bytes_per_row = width / 8;
+        let sprite_len = height * bytes_per_row;
+
+        let w = self.env.display_width as u32;
+        let h = self.env.display_height as u32;
+        let gx = gx as u32 % w;
+        let gy = gy as u32 % h;
+        let mut collided_rows = vec![false; height];
+        let mut addr = addr as usize;
+        let planes = self.env.planes;
+
+        for plane in (0..DISPLAY_PLANES).filter(|p| planes & (1 << p) != 0) {
+            let rows: Vec<u16> = {
+                if let Some(block) = self.mem.block(addr, sprite_len) {
+                    block.chunks(bytes_per_row)
+                        .map(|row| row.iter().fold(0, |acc, b| (acc << 8) | *b as u16))
+                        .collect()
+                } else {
+                    return Err(self.fault(addr, MemoryAccess::Read));
+                }
+            };
+
+            for (y, row) in rows.into_iter().enumerate() {
+                for x in 0..width {
+                    if row & (1 << (width - 1 - x)) != 0 {
+                        let lx = gx + x as u32;
+                        let ly = gy + y as u32;
 
-        self.set_v(0xF, 0);
+                        if self.quirks.clipping && (lx >= w || ly >= h) {
+                            continue;
+                        }
 
-        for (y, pixel) in pixels.into_iter().enumerate() {
-            for x in 0..7 {
-                if pixel & (0x80 >> x) != 0 {
-                    let lx = gx as u32 + x as u32;
-                    let ly = gy as u32 + y as u32;
-                    let white = self.env.pixel(lx, ly);
+                        let white = self.env.plane_pixel(plane, lx, ly);
 
-                    if white {
-                        self.set_v(0xF, 1);
-                    }
+                        if white {
+                            collided_rows[y] = true;
+                        }
 
-                    self.env.set_pixel(lx, ly, !white);
+                        self.env.set_plane_pixel(plane, lx, ly, !white);
+                    }
                 }
             }
+
+            addr += sprite_len;
+        }
+
+        let collided_rows = collided_rows.into_iter().filter(|c| *c).count() as u8;
+
+        if self.env.is_hires() {
+            self.set_v(0xF, collided_rows);
+        } else {
+            self.set_v(0xF, if collided_rows > 0 { 1 } else { 0 });
         }
+
+        Ok(())
     }
 
     pub fn press_key(&mut self, key: u8) {
@@ -158,198 +385,236 @@ impl CPU {
     }
 
     pub fn tick(&mut self) {
+        if let Interrupt::AwaitVBlank = self.interrupt {
+            self.interrupt = Interrupt::None;
+        }
+
         if self.regs.dt > 0 { self.regs.dt -= 1; }
         if self.regs.st > 0 { self.regs.st -= 1; }
     }
 
-    pub fn execute(&mut self, opcode: u16) {
-        let op = ((opcode & 0xF000) >> 12) as u8;
-        let n2 = ((opcode & 0x0F00) >> 8) as u8;
-        let n3 = ((opcode & 0x00F0) >> 4) as u8;
-        let n4 = (opcode & 0x000F) as u8;
-        let b2 = (opcode & 0x00FF) as u8;
-        let c2 = (opcode & 0x0FFF) as u16;
-
-        match op {
-            0x0 => match b2 {
-                // CLS
-                0xE0 => self.env.clear_screen(),
-                // RET
-                0xEE => self.ret(),
-                // SYS, ignore
-                _ => ()
-            },
+    pub fn execute(&mut self, opcode: u16) -> Result<(), CPUError> {
+        match Instruction::decode(opcode) {
+            Ok(inst) => self.execute_instruction(inst),
+            Err(_) => Err(self.unknown_inst(opcode))
+        }
+    }
 
-            // JP
-            0x1 => self.jump(c2),
-            // CALL
-            0x2 => self.call(c2),
-            // SE Vx, y
-            0x3 => if self.v(n2) == b2 { self.skip() },
-            // SNE Vx, y
-            0x4 => if self.v(n2) != b2 { self.skip() },
-            // SE Vx, Vy
-            0x5 => if self.v(n2) == self.v(n3) { self.skip() },
-            // LD Vx, y
-            0x6 => self.set_v(n2, b2),
-            // ADD Vx, y
-            0x7 => {
-                let v = self.v(n2);
-                self.set_v(n2, (Wrapping(b2) + Wrapping(v)).0);
+    pub fn execute_instruction(&mut self, inst: Instruction) -> Result<(), CPUError> {
+        match inst {
+            // SYS, ignore
+            Instruction::Sys { .. } => (),
+            Instruction::Cls => self.env.clear_screen(),
+            Instruction::Ret => self.ret()?,
+            Instruction::ScrollDown { n } => self.env.scroll(0, n as i32),
+            Instruction::ScrollUp { n } => self.env.scroll(0, -(n as i32)),
+            Instruction::ScrollRight => self.env.scroll(4, 0),
+            Instruction::ScrollLeft => self.env.scroll(-4, 0),
+            Instruction::Exit => self.interrupt = Interrupt::Exit,
+            Instruction::Low => self.env.set_hires(false),
+            Instruction::High => self.env.set_hires(true),
+            Instruction::Jp { addr } => self.jump(addr),
+            Instruction::Call { addr } => self.call(addr)?,
+            Instruction::Se { x, byte } => if self.v(x) == byte { self.skip() },
+            Instruction::Sne { x, byte } => if self.v(x) != byte { self.skip() },
+            Instruction::SeReg { x, y } => if self.v(x) == self.v(y) { self.skip() },
+            Instruction::StoreRange { x, y } => {
+                let addr = self.regs.i as usize;
+                let regs: Vec<u8> = register_range(x, y).map(|r| self.v(r)).collect();
+
+                match self.mem.block_mut(addr, regs.len()) {
+                    Some(block) => block.copy_from_slice(&regs),
+                    None => return Err(self.fault(addr, MemoryAccess::Write))
+                }
             },
-            // LD Vx, Vy
-            0x8 => {
-                let x = self.v(n2);
-                let y = self.v(n3);
-
-                match n4 {
-                    // LD Vx, Vy
-                    0x0 => self.set_v(n2, y),
-                    // OR Vx, Vy
-                    0x1 => self.set_v(n2, x | y),
-                    // AND Vx, Vy
-                    0x2 => self.set_v(n2, x & y),
-                    // XOR Vx, Vy
-                    0x3 => self.set_v(n2, x ^ y),
-                    // ADD Vx, Vy
-                    0x4 => {
-                        let carry = if x as u16 + y as u16 > std::u8::MAX as u16 { 1 } else { 0 };
-                        self.set_v(0xF, carry);
-                        self.set_v(n2, (Wrapping(x) + Wrapping(y)).0);
-                    },
-                    // SUB Vx, Vy / SUBN Vx, Vy
-                    0x5 | 0x7 => {
-                        let borrow = if n4 == 0x5 { x <= y } else { y <= x };
-                        self.set_v(0xF, if !borrow { 1 } else { 0 });
-                        self.set_v(n2, (Wrapping(x) - Wrapping(y)).0);
-                    },
-                    // SHR Vx, Vy
-                    0x6 => {
-                        self.set_v(0xF, y & 0x1);
-                        self.set_v(n2, y >> 1);
-                    },
-                    // SHL Vx, Vy
-                    0xE => {
-                        self.set_v(0xF, (y & 0x80) >> 7);
-                        self.set_v(n2, y << 1);
-                    },
+            Instruction::LoadRange { x, y } => {
+                let addr = self.regs.i as usize;
+                let range: Vec<u8> = register_range(x, y).collect();
+
+                let values = match self.mem.block(addr, range.len()) {
+                    Some(block) => block.to_vec(),
+                    None => return Err(self.fault(addr, MemoryAccess::Read))
+                };
 
-                    _ => unknown_inst()
+                for (r, v) in range.into_iter().zip(values) {
+                    self.set_v(r, v);
                 }
             },
-            // SNE Vx, Vy
-            0x9 => if self.v(n2) != self.v(n3) { self.skip() },
-            // LD I, x
-            0xA => self.regs.i = c2,
-            // JP V0, x
-            0xB => {
-                let v0 = self.v(0x0);
-                self.jump(c2 + v0 as u16);
+            Instruction::Ld { x, byte } => self.set_v(x, byte),
+            Instruction::Add { x, byte } => {
+                let v = self.v(x);
+                self.set_v(x, (Wrapping(byte) + Wrapping(v)).0);
             },
-            // RND Vx, x
-            0xC => {
-                let r = self.rng.gen::<u8>();
-                self.set_v(n2, r & b2);
+            Instruction::LdReg { x, y } => {
+                let v = self.v(y);
+                self.set_v(x, v);
             },
+            Instruction::Or { x, y } => self.set_v_logic(x, self.v(x) | self.v(y)),
+            Instruction::And { x, y } => self.set_v_logic(x, self.v(x) & self.v(y)),
+            Instruction::Xor { x, y } => self.set_v_logic(x, self.v(x) ^ self.v(y)),
+            Instruction::AddReg { x, y } => {
+                let (vx, vy) = (self.v(x), self.v(y));
+                let carry = if vx as u16 + vy as u16 > u8::MAX as u16 { 1 } else { 0 };
+                self.set_v(0xF, carry);
+                self.set_v(x, (Wrapping(vx) + Wrapping(vy)).0);
+            },
+            Instruction::Sub { x, y } | Instruction::Subn { x, y } => {
+                let (vx, vy) = (self.v(x), self.v(y));
+                let borrow = if let Instruction::Sub { .. } = inst { vx <= vy } else { vy <= vx };
+                self.set_v(0xF, if !borrow { 1 } else { 0 });
+                self.set_v(x, (Wrapping(vx) - Wrapping(vy)).0);
+            },
+            Instruction::Shr { x, y } => {
+                let v = self.v(if self.quirks.shift { x } else { y });
+                self.set_v(0xF, v & 0x1);
+                self.set_v(x, v >> 1);
+            },
+            Instruction::Shl { x, y } => {
+                let v = self.v(if self.quirks.shift { x } else { y });
+                self.set_v(0xF, (v & 0x80) >> 7);
+                self.set_v(x, v << 1);
+            },
+            Instruction::SneReg { x, y } => if self.v(x) != self.v(y) { self.skip() },
+            Instruction::LdI { addr } => self.regs.i = addr,
+            Instruction::JpV0 { x, addr } => {
+                let v = self.v(if self.quirks.jump { x } else { 0x0 });
+                self.jump(addr.wrapping_add(v as u16));
+            },
+            Instruction::Rnd { x, byte } => {
+                let r = self.rng.next_u8();
+                self.set_v(x, r & byte);
+            },
+            Instruction::Drw { x, y, n } => {
+                let (vx, vy) = (self.v(x), self.v(y));
+                let i = self.regs.i;
+                self.draw(vx, vy, i, n)?;
 
-            0xE => {
-                match b2 {
-                    // SKP Vx
-                    0x9E => if self.env.is_key_pressed(self.v(n2)) { self.skip() },
-                    // SKNP Vx
-                    0xA1 => if !self.env.is_key_pressed(self.v(n2)) { self.skip() },
-
-                    _ => unknown_inst()
+                if self.quirks.display_wait {
+                    self.interrupt = Interrupt::AwaitVBlank;
                 }
             },
+            Instruction::Skp { x } => if self.env.is_key_pressed(self.v(x)) { self.skip() },
+            Instruction::Sknp { x } => if !self.env.is_key_pressed(self.v(x)) { self.skip() },
+            Instruction::LdILong => {
+                let next = self.regs.pc.wrapping_add(2);
+
+                match self.word(next) {
+                    Some(addr) => self.regs.i = addr,
+                    None => return Err(self.fault(next as usize, MemoryAccess::Fetch))
+                }
 
-            // DRW Vx, Vy, x
-            0xD => {
-                let x = self.v(n2);
-                let y = self.v(n3);
-                let i = self.regs.i;
-                self.draw(x, y, i, n4)
+                self.regs.pc = next;
             },
+            Instruction::Plane { n } => self.env.planes = n & ((1 << DISPLAY_PLANES) - 1),
+            Instruction::LdAudio => {
+                let addr = self.regs.i as usize;
 
-            0xF => {
-                match b2 {
-                    // LD Vx, DT
-                    0x07 => {
-                        let dt = self.regs.dt;
-                        self.set_v(n2, dt);
-                    },
-                    // LD Vx, K
-                    0x0A => self.interrupt = Interrupt::AwaitKey(n2),
-                    // LD DT, Vx
-                    0x15 => self.regs.dt = self.v(n2),
-                    // LD ST, Vx
-                    0x18 => self.regs.st = self.v(n2),
-                    // ADD I, Vx
-                    0x1E => self.regs.i = (Wrapping(self.regs.i) + Wrapping(self.v(n2) as u16)).0,
-                    // LD F, Vx
-                    0x29 => self.regs.i = 0x00 + 5 * self.v(n2) as u16,
-                    // LD B, Vx
-                    0x33 => {
-                        let v = self.v(n2);
-                        if let Some(block) = self.mem.block_mut(self.regs.i as usize, 3) {
-                            block[0] = v / 100;
-                            block[1] = (v / 10) % 10;
-                            block[2] = (v % 100) % 10;
-                        }
-                    },
-                    // LD [I], Vx
-                    0x55 => {
-                        for i in 0..n2+1 {
-                            let v = self.v(i);
-                            if self.mem.poke(self.regs.i as usize, v).is_none() {
-                                break;
-                            }
-                            self.regs.i += 1;
-                        }
-                    },
-                    // LD Vx, [I]
-                    0x65 => {
-                        for i in 0..n2+1 {
-                            if let Some(v) = self.mem.peek(self.regs.i as usize) {
-                                self.regs.set_v(i as usize, v);
-                            } else {
-                                break;
-                            }
-                            self.regs.i += 1;
-                        }
+                match self.mem.block(addr, 16) {
+                    Some(block) => self.env.audio_pattern.copy_from_slice(block),
+                    None => return Err(self.fault(addr, MemoryAccess::Read))
+                }
+            },
+            Instruction::LdVxDt { x } => {
+                let dt = self.regs.dt;
+                self.set_v(x, dt);
+            },
+            Instruction::LdVxK { x } => self.interrupt = Interrupt::AwaitKey(x),
+            Instruction::LdDt { x } => self.regs.dt = self.v(x),
+            Instruction::LdSt { x } => self.regs.st = self.v(x),
+            Instruction::AddI { x } => self.regs.i = (Wrapping(self.regs.i) + Wrapping(self.v(x) as u16)).0,
+            Instruction::LdF { x } => self.regs.i = (CHIP8_CHARACTERS_ADDR + 5 * (self.v(x) & 0xF) as usize) as u16,
+            Instruction::LdHf { x } => self.regs.i = (SCHIP8_CHARACTERS_ADDR + 10 * (self.v(x) & 0xF) as usize) as u16,
+            Instruction::LdB { x } => {
+                let v = self.v(x);
+                let i = self.regs.i as usize;
+                match self.mem.block_mut(i, 3) {
+                    Some(block) => {
+                        block[0] = v / 100;
+                        block[1] = (v / 10) % 10;
+                        block[2] = (v % 100) % 10;
                     },
+                    None => return Err(self.fault(i, MemoryAccess::Write))
+                }
+            },
+            Instruction::LdPitch { x } => self.env.audio_pitch = self.v(x),
+            Instruction::StoreRegs { x } => {
+                for i in 0..x+1 {
+                    let v = self.v(i);
+                    let addr = self.regs.i as usize;
+                    if self.mem.poke(addr, v).is_none() {
+                        return Err(self.fault(addr, MemoryAccess::Write));
+                    }
+                    self.regs.i = self.regs.i.wrapping_add(1);
+                }
 
-                    _ => unknown_inst()
+                if self.quirks.load_store {
+                    self.regs.i = self.regs.i.wrapping_sub(x as u16 + 1);
+                }
+            },
+            Instruction::LoadRegs { x } => {
+                for i in 0..x+1 {
+                    let addr = self.regs.i as usize;
+                    match self.mem.peek(addr) {
+                        Some(v) => self.set_v(i, v),
+                        None => return Err(self.fault(addr, MemoryAccess::Read))
+                    }
+                    self.regs.i = self.regs.i.wrapping_add(1);
                 }
-            }
 
-            _ => unknown_inst()
+                if self.quirks.load_store {
+                    self.regs.i = self.regs.i.wrapping_sub(x as u16 + 1);
+                }
+            },
+            Instruction::StoreFlags { x } => {
+                for i in 0..x+1 {
+                    self.rpl_flags[i as usize] = self.v(i);
+                }
+            },
+            Instruction::LoadFlags { x } => {
+                for i in 0..x+1 {
+                    let v = self.rpl_flags[i as usize];
+                    self.set_v(i, v);
+                }
+            }
         }
+
+        Ok(())
     }
 
-    fn fetch(&self) -> u16 {
-        if let Some(b) = self.mem.block(self.regs.pc as usize, 2) {
-            ((b[0] as u16) << 8) | (b[1] as u16)
-        } else {
-            panic!("Failed to fetch next instruction!! Is PC out of bounds?")
+    fn word(&self, addr: u16) -> Option<u16> {
+        self.mem.block(addr as usize, 2).map(|b| ((b[0] as u16) << 8) | (b[1] as u16))
+    }
+
+    fn fetch(&self) -> Result<u16, CPUError> {
+        match self.word(self.regs.pc) {
+            Some(opcode) => Ok(opcode),
+            None => Err(self.fault(self.regs.pc as usize, MemoryAccess::Fetch))
         }
     }
 
-    pub fn step(&mut self) -> bool {
+    /// Executes the next instruction, returning `Ok(false)` if the CPU is
+    /// waiting on an interrupt and nothing was executed.
+    pub fn step(&mut self) -> Result<bool, CPUError> {
         match self.interrupt {
             Interrupt::None => {
-                let opcode = self.fetch();
-                self.execute(opcode);
-                self.regs.pc += 2;
-                true
+                let opcode = self.fetch()?;
+                self.execute(opcode)?;
+                self.regs.pc = self.regs.pc.wrapping_add(2);
+                Ok(true)
             },
-            _ => false
+            _ => Ok(false)
         }
     }
 
-    pub fn run(&mut self, stop_at_0: bool) {
-        while !stop_at_0 || self.fetch() != 0x0000 {
-            self.step();
+    /// Runs until a `0x0000` opcode is reached (if `stop_at_0` is set) or
+    /// until the CPU blocks on an interrupt.
+    pub fn run(&mut self, stop_at_0: bool) -> Result<(), CPUError> {
+        while !stop_at_0 || self.fetch()? != 0x0000 {
+            if !self.step()? {
+                break;
+            }
         }
+
+        Ok(())
     }
 }
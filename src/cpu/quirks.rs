@@ -0,0 +1,66 @@
+/// Selects between the differing interpretations of ambiguous instructions
+/// across CHIP-8 platforms. The default matches modern interpreters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quirks {
+    /// 8XY6/8XYE shift Vx in place instead of shifting Vy into Vx.
+    pub shift: bool,
+    /// FX55/FX65 leave I unchanged instead of incrementing it.
+    pub load_store: bool,
+    /// BNNN jumps to NNN + VX (BXNN) instead of NNN + V0.
+    pub jump: bool,
+    /// 8XY1/8XY2/8XY3 reset VF to 0.
+    pub vf_reset: bool,
+    /// Sprites are clipped at the screen edges instead of wrapping around.
+    pub clipping: bool,
+    /// DXYN waits for the next timer tick before execution continues.
+    pub display_wait: bool,
+    /// DXY0 draws an 8x16 sprite in lores instead of a 16x16 one.
+    pub lores_dxy0: bool
+}
+
+impl Quirks {
+    pub fn cosmac_vip() -> Quirks {
+        Quirks {
+            shift: false,
+            load_store: false,
+            jump: false,
+            vf_reset: true,
+            clipping: true,
+            display_wait: true,
+            lores_dxy0: false
+        }
+    }
+
+    pub fn chip48() -> Quirks {
+        Quirks {
+            shift: true,
+            load_store: true,
+            jump: true,
+            vf_reset: false,
+            clipping: true,
+            display_wait: false,
+            lores_dxy0: false
+        }
+    }
+
+    /// SUPER-CHIP 1.1 inherits the CHIP-48 behaviour, and only draws 16x16
+    /// sprites in hires.
+    pub fn superchip() -> Quirks {
+        Quirks { lores_dxy0: true, ..Quirks::chip48() }
+    }
+
+    pub fn modern() -> Quirks {
+        Quirks::default()
+    }
+
+    /// Looks up a preset by the name used on command lines and in config files.
+    pub fn from_name(name: &str) -> Option<Quirks> {
+        match name.to_ascii_lowercase().as_str() {
+            "vip" | "cosmac-vip" => Some(Quirks::cosmac_vip()),
+            "chip48" | "chip-48" => Some(Quirks::chip48()),
+            "schip" | "superchip" => Some(Quirks::superchip()),
+            "modern" | "xochip" => Some(Quirks::modern()),
+            _ => None
+        }
+    }
+}
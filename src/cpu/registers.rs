@@ -6,6 +6,12 @@ pub struct Registers {
     v: [u8; 16]
 }
 
+impl Default for Registers {
+    fn default() -> Registers {
+        Registers::new()
+    }
+}
+
 impl Registers {
     pub fn new() -> Registers {
         Registers {
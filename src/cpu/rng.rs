@@ -0,0 +1,71 @@
+use rand;
+
+/// Source of the random numbers used by `RND Vx`.
+///
+/// Sources must be fully deterministic given their seed, and their position
+/// in the sequence must fit into a single word so save states can capture it.
+pub trait RandomSource {
+    /// The seed the sequence was started from.
+    fn seed(&self) -> u64;
+    /// Restarts the sequence from `seed`.
+    fn reseed(&mut self, seed: u64);
+    fn next_u8(&mut self) -> u8;
+    /// Current position in the sequence.
+    fn state(&self) -> u64;
+    /// Moves to a position previously returned by `state`.
+    fn set_state(&mut self, state: u64);
+}
+
+/// xorshift64* generator, the default `RandomSource`. It only uses integer
+/// arithmetic, so a given seed produces the same numbers on every machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XorShift {
+    seed: u64,
+    state: u64
+}
+
+/// xorshift gets stuck on a zero state, so it's replaced by this.
+const ZERO_STATE_REPLACEMENT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+fn nonzero(state: u64) -> u64 {
+    if state == 0 { ZERO_STATE_REPLACEMENT } else { state }
+}
+
+impl XorShift {
+    pub fn new(seed: u64) -> XorShift {
+        XorShift { seed, state: nonzero(seed) }
+    }
+
+    pub fn from_entropy() -> XorShift {
+        XorShift::new(rand::random())
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+impl RandomSource for XorShift {
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn reseed(&mut self, seed: u64) {
+        *self = XorShift::new(seed);
+    }
+
+    fn next_u8(&mut self) -> u8 {
+        (self.next_u64() >> 56) as u8
+    }
+
+    fn state(&self) -> u64 {
+        self.state
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.state = nonzero(state);
+    }
+}
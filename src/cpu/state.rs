@@ -0,0 +1,208 @@
+use super::{CPU, CPUEnvironment, Interrupt, STACK_SIZE};
+use super::registers::Registers;
+use super::memory::{Memory, CHIP8_MEMORY_SIZE};
+use super::error::StateError;
+use super::quirks::Quirks;
+
+pub const STATE_MAGIC: &[u8; 4] = b"CH8S";
+pub const STATE_VERSION: u8 = 3;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        match self.data.get(self.pos..self.pos + len) {
+            Some(bytes) => {
+                self.pos += len;
+                Ok(bytes)
+            },
+            None => Err(StateError::Truncated)
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, StateError> {
+        let b = self.bytes(2)?;
+        Ok(((b[0] as u16) << 8) | b[1] as u16)
+    }
+
+    fn u32(&mut self) -> Result<u32, StateError> {
+        Ok(((self.u16()? as u32) << 16) | self.u16()? as u32)
+    }
+
+    fn u64(&mut self) -> Result<u64, StateError> {
+        Ok(((self.u32()? as u64) << 32) | self.u32()? as u64)
+    }
+}
+
+fn quirk_bits(quirks: &Quirks) -> u8 {
+    [quirks.shift, quirks.load_store, quirks.jump, quirks.vf_reset, quirks.clipping, quirks.display_wait, quirks.lores_dxy0]
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (i, set)| if *set { bits | 1 << i } else { bits })
+}
+
+fn quirks_from_bits(bits: u8) -> Quirks {
+    Quirks {
+        shift: bits & 0x01 != 0,
+        load_store: bits & 0x02 != 0,
+        jump: bits & 0x04 != 0,
+        vf_reset: bits & 0x08 != 0,
+        clipping: bits & 0x10 != 0,
+        display_wait: bits & 0x20 != 0,
+        lores_dxy0: bits & 0x40 != 0
+    }
+}
+
+impl CPU {
+    /// Serializes the complete machine state into a versioned, big-endian
+    /// binary blob that `load_state` can restore.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mem = self.mem.block(0, self.mem.size()).unwrap_or(&[]);
+        let mut out = Vec::with_capacity(128 + self.env.display.len() + mem.len());
+
+        out.extend_from_slice(STATE_MAGIC);
+        out.push(STATE_VERSION);
+        out.push(quirk_bits(&self.quirks));
+
+        for x in 0..16 {
+            out.push(self.regs.v(x).unwrap_or(0));
+        }
+
+        out.extend_from_slice(&self.regs.i.to_be_bytes());
+        out.extend_from_slice(&self.regs.pc.to_be_bytes());
+        out.push(self.regs.dt);
+        out.push(self.regs.st);
+
+        out.push(self.stack.len() as u8);
+        for addr in &self.stack {
+            out.extend_from_slice(&addr.to_be_bytes());
+        }
+
+        match self.interrupt {
+            Interrupt::None => out.extend_from_slice(&[0, 0]),
+            Interrupt::AwaitKey(x) => out.extend_from_slice(&[1, x]),
+            Interrupt::AwaitVBlank => out.extend_from_slice(&[2, 0]),
+            Interrupt::Exit => out.extend_from_slice(&[3, 0])
+        }
+
+        out.extend_from_slice(&self.rpl_flags);
+        out.extend_from_slice(&self.rng.seed().to_be_bytes());
+        out.extend_from_slice(&self.rng.state().to_be_bytes());
+
+        let keys = self.env.keyboard.iter().enumerate()
+            .fold(0u16, |keys, (i, pressed)| if *pressed { keys | 1 << i } else { keys });
+        out.extend_from_slice(&keys.to_be_bytes());
+
+        out.push(self.env.display_width);
+        out.push(self.env.display_height);
+        out.push(self.env.planes);
+        out.extend_from_slice(&self.env.display);
+        out.extend_from_slice(&self.env.audio_pattern);
+        out.push(self.env.audio_pitch);
+
+        out.extend_from_slice(&(mem.len() as u32).to_be_bytes());
+        out.extend_from_slice(mem);
+
+        out
+    }
+
+    /// Restores a state produced by `save_state`. The CPU is left untouched
+    /// if the state can't be read.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        let mut r = Reader { data, pos: 0 };
+
+        if r.bytes(STATE_MAGIC.len()).map_err(|_| StateError::BadMagic)? != STATE_MAGIC {
+            return Err(StateError::BadMagic);
+        }
+
+        let version = r.u8()?;
+        if version != STATE_VERSION {
+            return Err(StateError::UnsupportedVersion(version));
+        }
+
+        let quirks = quirks_from_bits(r.u8()?);
+
+        let mut regs = Registers::new();
+        for (x, v) in r.bytes(16)?.iter().enumerate() {
+            regs.set_v(x, *v);
+        }
+
+        regs.i = r.u16()?;
+        regs.pc = r.u16()?;
+        regs.dt = r.u8()?;
+        regs.st = r.u8()?;
+
+        let stack_len = r.u8()? as usize;
+        if stack_len > STACK_SIZE {
+            return Err(StateError::Invalid("stack size"));
+        }
+
+        let mut stack = Vec::with_capacity(STACK_SIZE);
+        for _ in 0..stack_len {
+            stack.push(r.u16()?);
+        }
+
+        let interrupt = match (r.u8()?, r.u8()?) {
+            (0, _) => Interrupt::None,
+            (1, x) if Registers::valid_register_index(x as usize) => Interrupt::AwaitKey(x),
+            (2, _) => Interrupt::AwaitVBlank,
+            (3, _) => Interrupt::Exit,
+            _ => return Err(StateError::Invalid("interrupt"))
+        };
+
+        let mut rpl_flags = [0; 16];
+        rpl_flags.copy_from_slice(r.bytes(16)?);
+
+        let seed = r.u64()?;
+        let rng_state = r.u64()?;
+
+        let keys = r.u16()?;
+        let width = r.u8()?;
+        let height = r.u8()?;
+
+        let mut env = CPUEnvironment::new(width, height);
+
+        if env.display.is_empty() {
+            return Err(StateError::Invalid("display size"));
+        }
+
+        for (i, pressed) in env.keyboard.iter_mut().enumerate() {
+            *pressed = keys & (1 << i) != 0;
+        }
+
+        env.planes = r.u8()?;
+        let display_len = env.display.len();
+        env.display.copy_from_slice(r.bytes(display_len)?);
+        env.audio_pattern.copy_from_slice(r.bytes(16)?);
+        env.audio_pitch = r.u8()?;
+        env.display_version = self.env.display_version.wrapping_add(1);
+
+        let mem_size = r.u32()? as usize;
+        if mem_size < CHIP8_MEMORY_SIZE {
+            return Err(StateError::Invalid("memory size"));
+        }
+
+        let contents = r.bytes(mem_size)?;
+        let mut mem = Memory::with_size(mem_size);
+        mem.load(0, contents);
+
+        self.quirks = quirks;
+        self.regs = regs;
+        self.stack = stack;
+        self.interrupt = interrupt;
+        self.rpl_flags = rpl_flags;
+        self.rng.reseed(seed);
+        self.rng.set_state(rng_state);
+        self.env = env;
+        self.mem = mem;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,582 @@
+use std::fmt;
+use std::fmt::Write;
+
+use super::cpu::CPU;
+use super::cpu::error::{CPUError, MemoryAccess};
+use super::cpu::instruction::Instruction;
+
+/// Upper bound on instructions executed by a single step-over or step-out, so
+/// a subroutine that never returns can't hang the debugger.
+pub const STEP_LIMIT: usize = 1_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Register {
+    V(u8),
+    I,
+    DT,
+    ST
+}
+
+impl Register {
+    fn parse(text: &str) -> Option<Register> {
+        let upper = text.to_ascii_uppercase();
+
+        match upper.as_str() {
+            "I" => Some(Register::I),
+            "DT" => Some(Register::DT),
+            "ST" => Some(Register::ST),
+            _ if upper.len() == 2 && upper.starts_with('V') =>
+                u8::from_str_radix(&upper[1..], 16).ok().map(Register::V),
+            _ => None
+        }
+    }
+
+    fn value(&self, cpu: &CPU) -> u16 {
+        match *self {
+            Register::V(x) => cpu.regs.v(x as usize).unwrap_or(0) as u16,
+            Register::I => cpu.regs.i,
+            Register::DT => cpu.regs.dt as u16,
+            Register::ST => cpu.regs.st as u16
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Register::V(x) => write!(f, "V{:X}", x),
+            Register::I => write!(f, "I"),
+            Register::DT => write!(f, "DT"),
+            Register::ST => write!(f, "ST")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge
+}
+
+impl Compare {
+    fn parse(text: &str) -> Option<Compare> {
+        match text {
+            "==" => Some(Compare::Eq),
+            "!=" => Some(Compare::Ne),
+            "<" => Some(Compare::Lt),
+            "<=" => Some(Compare::Le),
+            ">" => Some(Compare::Gt),
+            ">=" => Some(Compare::Ge),
+            _ => None
+        }
+    }
+}
+
+impl fmt::Display for Compare {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Compare::Eq => "==",
+            Compare::Ne => "!=",
+            Compare::Lt => "<",
+            Compare::Le => "<=",
+            Compare::Gt => ">",
+            Compare::Ge => ">="
+        };
+
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Condition {
+    pub reg: Register,
+    pub cmp: Compare,
+    pub value: u16
+}
+
+impl Condition {
+    pub fn holds(&self, cpu: &CPU) -> bool {
+        let v = self.reg.value(cpu);
+
+        match self.cmp {
+            Compare::Eq => v == self.value,
+            Compare::Ne => v != self.value,
+            Compare::Lt => v < self.value,
+            Compare::Le => v <= self.value,
+            Compare::Gt => v > self.value,
+            Compare::Ge => v >= self.value
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub addr: u16,
+    pub condition: Option<Condition>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Watch {
+    Read,
+    Write,
+    Access
+}
+
+impl Watch {
+    fn matches(&self, access: MemoryAccess) -> bool {
+        match *self {
+            Watch::Read => access == MemoryAccess::Read,
+            Watch::Write => access == MemoryAccess::Write,
+            Watch::Access => access != MemoryAccess::Fetch
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub addr: usize,
+    pub len: usize,
+    pub watch: Watch
+}
+
+/// Why execution was stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    Breakpoint(u16),
+    Watchpoint { addr: usize, access: MemoryAccess },
+    Step,
+    /// The CPU is waiting on an interrupt, e.g. a key press.
+    Blocked,
+    /// `STEP_LIMIT` instructions were executed without reaching the target.
+    Limit,
+    Error(CPUError)
+}
+
+impl fmt::Display for Stop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Stop::Breakpoint(addr) => write!(f, "breakpoint at {:04X}", addr),
+            Stop::Watchpoint { addr, access } => write!(f, "watchpoint: {} of {:04X}", access, addr),
+            Stop::Step => write!(f, "stepped"),
+            Stop::Blocked => write!(f, "CPU is waiting on an interrupt"),
+            Stop::Limit => write!(f, "gave up after {} instructions", STEP_LIMIT),
+            Stop::Error(err) => write!(f, "{}", err)
+        }
+    }
+}
+
+/// Output of a debugger command, and whether the emulator should resume.
+pub struct Response {
+    pub text: String,
+    pub resume: bool
+}
+
+const HELP: &str = "\
+s, step [n]               execute n instructions (default 1)
+n, next                   step over CALL
+o, out                    run until the current subroutine returns
+c, continue               resume execution
+b, break <addr> [r op v]  set a breakpoint, optionally when e.g. V0 == 0x0A
+d, delete <addr>          remove breakpoints at addr
+w, watch <addr> [len] [r|w|rw]
+                          stop when memory is read and/or written
+bl, breakpoints           list breakpoints and watchpoints
+r, regs                   show registers
+bt, stack                 show the call stack
+m, mem <addr> [len]       dump memory
+l, list [addr] [n]        disassemble instructions
+h, help                   show this help";
+
+fn parse_number(text: &str) -> Option<usize> {
+    let lower = text.to_ascii_lowercase();
+
+    if let Some(hex) = lower.strip_prefix("0x") {
+        usize::from_str_radix(hex, 16).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+/// Parses an address, which has to be within `cpu`'s memory.
+fn parse_address(cpu: &CPU, text: Option<&&str>) -> Result<usize, String> {
+    let text = text.ok_or("expected an address")?;
+    let addr = parse_number(text).ok_or_else(|| format!("invalid address {}", text))?;
+
+    if addr >= cpu.mem.size() {
+        return Err(format!("address {} is outside memory, which ends at {:04X}", text, cpu.mem.size() - 1));
+    }
+
+    Ok(addr)
+}
+
+fn next_instruction(cpu: &CPU) -> Option<Instruction> {
+    cpu.mem.block(cpu.regs.pc as usize, 2)
+        .and_then(|b| Instruction::decode(((b[0] as u16) << 8) | b[1] as u16).ok())
+}
+
+/// Memory ranges the instruction will access when executed by `cpu`.
+pub fn accesses(cpu: &CPU, inst: &Instruction) -> Vec<(usize, usize, MemoryAccess)> {
+    let i = cpu.regs.i as usize;
+
+    match *inst {
+        Instruction::Drw { n, .. } => {
+            // DXY0 draws 16x16 sprites, or 8x16 ones like `CPU::draw` in lores
+            // with the `lores_dxy0` quirk.
+            let len = match n {
+                0 if cpu.quirks.lores_dxy0 && !cpu.env.is_hires() => 16,
+                0 => 32,
+                n => n as usize
+            };
+            let planes = cpu.env.planes.count_ones() as usize;
+            vec![(i, len * planes, MemoryAccess::Read)]
+        },
+        Instruction::LdB { .. } => vec![(i, 3, MemoryAccess::Write)],
+        Instruction::StoreRegs { x } => vec![(i, x as usize + 1, MemoryAccess::Write)],
+        Instruction::LoadRegs { x } => vec![(i, x as usize + 1, MemoryAccess::Read)],
+        Instruction::StoreRange { x, y } => vec![(i, (x as isize - y as isize).unsigned_abs() + 1, MemoryAccess::Write)],
+        Instruction::LoadRange { x, y } => vec![(i, (x as isize - y as isize).unsigned_abs() + 1, MemoryAccess::Read)],
+        Instruction::LdAudio => vec![(i, 16, MemoryAccess::Read)],
+        _ => Vec::new()
+    }
+}
+
+pub fn format_registers(cpu: &CPU) -> String {
+    let mut out = String::new();
+
+    for x in 0..16 {
+        let _ = write!(out, "V{:X}={:02X} ", x, cpu.regs.v(x).unwrap_or(0));
+        if x == 7 {
+            out.push('\n');
+        }
+    }
+
+    let _ = write!(out, "\nPC={:04X} I={:04X} DT={:02X} ST={:02X} SP={} {:?}",
+        cpu.regs.pc, cpu.regs.i, cpu.regs.dt, cpu.regs.st, cpu.stack().len(), cpu.interrupt);
+
+    out
+}
+
+#[derive(Default)]
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    /// Breakpoint address to ignore once, so continuing from a breakpoint
+    /// doesn't immediately stop again.
+    resume_from: Option<u16>
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger::default()
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn watchpoints(&self) -> &[Watchpoint] {
+        &self.watchpoints
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16, condition: Option<Condition>) {
+        self.breakpoints.push(Breakpoint { addr, condition });
+    }
+
+    pub fn remove_breakpoints(&mut self, addr: u16) {
+        self.breakpoints.retain(|b| b.addr != addr);
+        self.watchpoints.retain(|w| w.addr != addr as usize);
+    }
+
+    pub fn add_watchpoint(&mut self, addr: usize, len: usize, watch: Watch) {
+        self.watchpoints.push(Watchpoint { addr, len, watch });
+    }
+
+    /// Checks whether the instruction at PC should stop execution before it runs.
+    pub fn check(&mut self, cpu: &CPU) -> Option<Stop> {
+        let pc = cpu.regs.pc;
+
+        if self.resume_from.take() == Some(pc) {
+            return None;
+        }
+
+        let hit = self.breakpoints.iter()
+            .any(|b| b.addr == pc && b.condition.map(|c| c.holds(cpu)).unwrap_or(true));
+
+        if hit {
+            return Some(Stop::Breakpoint(pc));
+        }
+
+        if self.watchpoints.is_empty() {
+            return None;
+        }
+
+        let inst = next_instruction(cpu)?;
+
+        for (start, len, access) in accesses(cpu, &inst) {
+            for w in &self.watchpoints {
+                if w.watch.matches(access) && start < w.addr.saturating_add(w.len) && w.addr < start.saturating_add(len) {
+                    return Some(Stop::Watchpoint { addr: start.max(w.addr), access });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Call before resuming from a stop, so the breakpoint at PC is skipped.
+    pub fn resume(&mut self, cpu: &CPU) {
+        self.resume_from = Some(cpu.regs.pc);
+    }
+
+    pub fn step(&mut self, cpu: &mut CPU) -> Stop {
+        match cpu.step() {
+            Ok(true) => Stop::Step,
+            Ok(false) => Stop::Blocked,
+            Err(err) => Stop::Error(err)
+        }
+    }
+
+    /// Runs until `done` holds, stopping early on breakpoints and watchpoints.
+    fn run_until<F: Fn(&CPU) -> bool>(&mut self, cpu: &mut CPU, done: F) -> Stop {
+        self.resume(cpu);
+
+        for _ in 0..STEP_LIMIT {
+            if let Some(stop) = self.check(cpu) {
+                return stop;
+            }
+
+            match self.step(cpu) {
+                Stop::Step => (),
+                stop => return stop
+            }
+
+            if done(cpu) {
+                return Stop::Step;
+            }
+        }
+
+        Stop::Limit
+    }
+
+    /// Steps over CALL instructions by running until the subroutine returns.
+    pub fn step_over(&mut self, cpu: &mut CPU) -> Stop {
+        match next_instruction(cpu) {
+            Some(Instruction::Call { .. }) => {
+                let ret = cpu.regs.pc.wrapping_add(2);
+                let depth = cpu.stack().len();
+                self.run_until(cpu, |cpu| cpu.regs.pc == ret && cpu.stack().len() == depth)
+            },
+            _ => self.step(cpu)
+        }
+    }
+
+    /// Runs until the current subroutine returns to its caller.
+    pub fn step_out(&mut self, cpu: &mut CPU) -> Stop {
+        let depth = cpu.stack().len();
+
+        if depth == 0 {
+            return self.step(cpu);
+        }
+
+        self.run_until(cpu, |cpu| cpu.stack().len() < depth)
+    }
+
+    fn parse_breakpoint(cpu: &CPU, args: &[&str]) -> Result<Breakpoint, String> {
+        let addr = parse_address(cpu, args.first())?;
+
+        let condition = match args.len() {
+            1 => None,
+            4 => {
+                let reg = Register::parse(args[1]).ok_or_else(|| format!("unknown register {}", args[1]))?;
+                let cmp = Compare::parse(args[2]).ok_or_else(|| format!("unknown comparison {}", args[2]))?;
+                let value = parse_number(args[3]).filter(|value| *value <= 0xFFFF)
+                    .ok_or_else(|| format!("invalid value {}", args[3]))?;
+                Some(Condition { reg, cmp, value: value as u16 })
+            },
+            _ => return Err("expected: break <addr> [<reg> <op> <value>]".to_string())
+        };
+
+        Ok(Breakpoint { addr: addr as u16, condition })
+    }
+
+    fn list(cpu: &CPU, addr: usize, count: usize) -> String {
+        let mut out = String::new();
+        let mut addr = addr;
+
+        for _ in 0..count {
+            let inst = cpu.mem.block(addr, 2).map(|b| ((b[0] as u16) << 8) | b[1] as u16);
+
+            let text = match inst.map(Instruction::decode) {
+                Some(Ok(Instruction::LdILong)) => match cpu.mem.block(addr + 2, 2) {
+                    Some(b) => format!("LD I, LONG 0x{:02X}{:02X}", b[0], b[1]),
+                    None => "LD I, LONG".to_string()
+                },
+                Some(Ok(inst)) => inst.to_string(),
+                Some(Err(_)) => format!("DW 0x{:04X}", inst.unwrap_or(0)),
+                None => break
+            };
+
+            let marker = if addr == cpu.regs.pc as usize { "=>" } else { "  " };
+            let _ = writeln!(out, "{} {:04X}  {}", marker, addr, text);
+
+            addr += match inst.map(Instruction::decode) {
+                Some(Ok(inst)) => inst.size() as usize,
+                _ => 2
+            };
+        }
+
+        out
+    }
+
+    fn dump(cpu: &CPU, addr: usize, len: usize) -> String {
+        let mut out = String::new();
+
+        let end = addr.saturating_add(len).min(cpu.mem.size());
+
+        for row in (addr..end).step_by(16) {
+            let bytes: Vec<String> = (row..(row + 16).min(end))
+                .filter_map(|a| cpu.mem.peek(a))
+                .map(|b| format!("{:02X}", b))
+                .collect();
+
+            if bytes.is_empty() {
+                break;
+            }
+
+            let _ = writeln!(out, "{:04X}  {}", row, bytes.join(" "));
+        }
+
+        out
+    }
+
+    fn stop_text(cpu: &CPU, stop: Stop) -> String {
+        format!("{}\n{}", stop, Debugger::list(cpu, cpu.regs.pc as usize, 1).trim_end())
+    }
+
+    /// Executes a REPL command line against `cpu`.
+    pub fn command(&mut self, cpu: &mut CPU, line: &str) -> Response {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let reply = |text: String| Response { text, resume: false };
+
+        let (cmd, args) = match args.split_first() {
+            Some((cmd, args)) => (*cmd, args),
+            None => return reply(String::new())
+        };
+
+        match cmd {
+            "s" | "step" => {
+                let n = args.first().and_then(|n| parse_number(n)).unwrap_or(1);
+                let mut stop = Stop::Step;
+
+                for i in 0..n {
+                    if i > 0 {
+                        if let Some(s) = self.check(cpu) {
+                            stop = s;
+                            break;
+                        }
+                    }
+
+                    stop = self.step(cpu);
+                    if stop != Stop::Step {
+                        break;
+                    }
+                }
+
+                reply(Debugger::stop_text(cpu, stop))
+            },
+            "n" | "next" => {
+                let stop = self.step_over(cpu);
+                reply(Debugger::stop_text(cpu, stop))
+            },
+            "o" | "out" => {
+                let stop = self.step_out(cpu);
+                reply(Debugger::stop_text(cpu, stop))
+            },
+            "c" | "continue" => {
+                self.resume(cpu);
+                Response { text: String::new(), resume: true }
+            },
+            "b" | "break" => match Debugger::parse_breakpoint(cpu, args) {
+                Ok(b) => {
+                    self.add_breakpoint(b.addr, b.condition);
+                    reply(format!("breakpoint at {:04X}", b.addr))
+                },
+                Err(err) => reply(err)
+            },
+            "d" | "delete" => match parse_address(cpu, args.first()) {
+                Ok(addr) => {
+                    self.remove_breakpoints(addr as u16);
+                    reply(format!("deleted breakpoints at {:04X}", addr))
+                },
+                Err(err) => reply(err)
+            },
+            "w" | "watch" => {
+                let addr = match parse_address(cpu, args.first()) {
+                    Ok(addr) => addr,
+                    Err(err) => return reply(err)
+                };
+
+                // Watching past the end of memory would never trigger.
+                let len = args.get(1).and_then(|a| parse_number(a)).unwrap_or(1).min(cpu.mem.size() - addr);
+
+                let watch = match args.last().copied() {
+                    Some("r") => Watch::Read,
+                    Some("w") => Watch::Write,
+                    _ => Watch::Access
+                };
+
+                self.add_watchpoint(addr, len, watch);
+                reply(format!("watching {:04X}..{:04X} ({:?})", addr, addr + len, watch))
+            },
+            "bl" | "breakpoints" => {
+                let mut out = String::new();
+
+                for b in &self.breakpoints {
+                    let _ = match b.condition {
+                        Some(c) => writeln!(out, "break {:04X} if {} {} 0x{:X}", b.addr, c.reg, c.cmp, c.value),
+                        None => writeln!(out, "break {:04X}", b.addr)
+                    };
+                }
+
+                for w in &self.watchpoints {
+                    let _ = writeln!(out, "watch {:04X}..{:04X} ({:?})", w.addr, w.addr.saturating_add(w.len), w.watch);
+                }
+
+                reply(out.trim_end().to_string())
+            },
+            "r" | "regs" => reply(format_registers(cpu)),
+            "bt" | "stack" => {
+                let frames: Vec<String> = cpu.stack().iter().rev()
+                    .enumerate()
+                    .map(|(i, addr)| format!("#{} {:04X}", i, addr))
+                    .collect();
+
+                reply(if frames.is_empty() { "stack is empty".to_string() } else { frames.join("\n") })
+            },
+            "m" | "mem" => match parse_address(cpu, args.first()) {
+                Ok(addr) => {
+                    let len = args.get(1).and_then(|a| parse_number(a)).unwrap_or(64);
+                    reply(Debugger::dump(cpu, addr, len).trim_end().to_string())
+                },
+                Err(err) => reply(err)
+            },
+            "l" | "list" => {
+                let addr = match args.first() {
+                    Some(_) => match parse_address(cpu, args.first()) {
+                        Ok(addr) => addr,
+                        Err(err) => return reply(err)
+                    },
+                    None => cpu.regs.pc as usize
+                };
+                let count = args.get(1).and_then(|a| parse_number(a)).unwrap_or(8);
+                reply(Debugger::list(cpu, addr, count).trim_end().to_string())
+            },
+            "h" | "help" => reply(HELP.to_string()),
+            _ => reply(format!("unknown command `{}`, try `help`", cmd))
+        }
+    }
+}
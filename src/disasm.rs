@@ -0,0 +1,212 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::cpu::instruction::Instruction;
+
+/// What a run of bytes in the program was identified as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Code(Instruction),
+    /// A row of sprite data referenced by `LD I`.
+    Sprite(u8),
+    Data(Vec<u8>)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub addr: u16,
+    pub bytes: Vec<u8>,
+    pub item: Item
+}
+
+pub struct Disassembly {
+    pub lines: Vec<Line>,
+    pub labels: BTreeMap<u16, String>
+}
+
+const DATA_BYTES_PER_LINE: usize = 8;
+
+fn opcode_at(program: &[u8], offset: usize) -> Option<u16> {
+    if offset + 1 < program.len() {
+        Some(((program[offset] as u16) << 8) | program[offset + 1] as u16)
+    } else {
+        None
+    }
+}
+
+/// Follows control flow from `origin` and returns the addresses of every
+/// reachable instruction along with the targets of `LD I`.
+fn trace(program: &[u8], origin: u16) -> (BTreeMap<u16, Instruction>, BTreeSet<u16>) {
+    let end = origin as usize + program.len();
+    let mut code = BTreeMap::new();
+    let mut data_refs = BTreeSet::new();
+    let mut pending = vec![origin];
+
+    while let Some(addr) = pending.pop() {
+        if (addr as usize) < origin as usize || addr as usize >= end || code.contains_key(&addr) {
+            continue;
+        }
+
+        let inst = match opcode_at(program, (addr - origin) as usize).map(Instruction::decode) {
+            Some(Ok(inst)) => inst,
+            _ => continue
+        };
+
+        code.insert(addr, inst);
+        let next = addr.wrapping_add(inst.size());
+
+        match inst {
+            Instruction::Ret | Instruction::Exit => (),
+            Instruction::Jp { addr: target } => pending.push(target),
+            Instruction::JpV0 { addr: target, .. } => pending.push(target),
+            Instruction::Call { addr: target } => {
+                pending.push(target);
+                pending.push(next);
+            },
+            Instruction::Se { .. } | Instruction::Sne { .. } | Instruction::SeReg { .. } |
+            Instruction::SneReg { .. } | Instruction::Skp { .. } | Instruction::Sknp { .. } => {
+                let skipped = match opcode_at(program, (next.wrapping_sub(origin)) as usize) {
+                    Some(0xF000) => 4,
+                    _ => 2
+                };
+
+                pending.push(next);
+                pending.push(next.wrapping_add(skipped));
+            },
+            Instruction::LdI { addr: target } => {
+                data_refs.insert(target);
+                pending.push(next);
+            },
+            Instruction::LdILong => {
+                if let Some(target) = opcode_at(program, (addr - origin) as usize + 2) {
+                    data_refs.insert(target);
+                }
+                pending.push(next);
+            },
+            _ => pending.push(next)
+        }
+    }
+
+    (code, data_refs)
+}
+
+pub fn disassemble(program: &[u8], origin: u16) -> Disassembly {
+    let (code, data_refs) = trace(program, origin);
+    let mut labels = BTreeMap::new();
+
+    for inst in code.values() {
+        match *inst {
+            Instruction::Jp { addr } | Instruction::Call { addr } | Instruction::JpV0 { addr, .. }
+                if code.contains_key(&addr) => {
+                labels.insert(addr, format!("L{:03X}", addr));
+            },
+            _ => ()
+        }
+    }
+
+    let end = origin as usize + program.len();
+
+    for addr in &data_refs {
+        if *addr >= origin && (*addr as usize) < end && !code.contains_key(addr) {
+            labels.insert(*addr, format!("S{:03X}", addr));
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut addr = origin as usize;
+    let mut in_sprite = false;
+
+    while addr < end {
+        let offset = addr - origin as usize;
+        let a = addr as u16;
+
+        if let Some(inst) = code.get(&a) {
+            let size = inst.size() as usize;
+            let bytes = program[offset..(offset + size).min(program.len())].to_vec();
+            lines.push(Line { addr: a, bytes, item: Item::Code(*inst) });
+            addr += size;
+            in_sprite = false;
+            continue;
+        }
+
+        if labels.contains_key(&a) {
+            in_sprite = data_refs.contains(&a);
+        }
+
+        if in_sprite {
+            lines.push(Line { addr: a, bytes: vec![program[offset]], item: Item::Sprite(program[offset]) });
+            addr += 1;
+            continue;
+        }
+
+        let mut bytes = Vec::new();
+
+        while addr < end && bytes.len() < DATA_BYTES_PER_LINE {
+            let a = addr as u16;
+
+            if code.contains_key(&a) || (!bytes.is_empty() && labels.contains_key(&a)) {
+                break;
+            }
+
+            bytes.push(program[addr - origin as usize]);
+            addr += 1;
+        }
+
+        lines.push(Line { addr: a, bytes: bytes.clone(), item: Item::Data(bytes) });
+    }
+
+    Disassembly { lines, labels }
+}
+
+impl Disassembly {
+    fn operand(&self, addr: u16) -> String {
+        match self.labels.get(&addr) {
+            Some(label) => label.clone(),
+            None => format!("0x{:03X}", addr)
+        }
+    }
+
+    fn format_item(&self, line: &Line) -> String {
+        match line.item {
+            Item::Code(Instruction::Jp { addr }) => format!("JP {}", self.operand(addr)),
+            Item::Code(Instruction::Call { addr }) => format!("CALL {}", self.operand(addr)),
+            Item::Code(Instruction::JpV0 { addr, .. }) => format!("JP V0, {}", self.operand(addr)),
+            Item::Code(Instruction::LdI { addr }) => format!("LD I, {}", self.operand(addr)),
+            Item::Code(Instruction::LdILong) if line.bytes.len() == 4 => {
+                let addr = ((line.bytes[2] as u16) << 8) | line.bytes[3] as u16;
+                match self.labels.get(&addr) {
+                    Some(label) => format!("LD I, LONG {}", label),
+                    None => format!("LD I, LONG 0x{:04X}", addr)
+                }
+            },
+            Item::Code(inst) => inst.to_string(),
+            Item::Sprite(row) => format!("DB 0b{:08b}", row),
+            Item::Data(ref bytes) => {
+                let bytes: Vec<String> = bytes.iter().map(|b| format!("0x{:02X}", b)).collect();
+                format!("DB {}", bytes.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            if let Some(label) = self.labels.get(&line.addr) {
+                writeln!(f, "{}:", label)?;
+            }
+
+            let bytes: Vec<String> = line.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+            write!(f, "    {:<32} ; {:04X}  {}", self.format_item(line), line.addr, bytes.join(" "))?;
+
+            if let Item::Sprite(row) = line.item {
+                let bitmap: String = (0..8).map(|x| if row & (0x80 >> x) != 0 { '#' } else { '.' }).collect();
+                write!(f, "  {}", bitmap)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
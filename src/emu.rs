@@ -1,138 +1,494 @@
-use chip8;
-use phf;
-use phf::phf_map;
-
-use sdl2;
-use sdl2::event::Event;
-
-use std::time::Instant;
-
-const WINDOW_TITLE: &str = "CHIP-8 Emulator";
-
-const PIXEL_WIDTH: u32 = 16;
-const PIXEL_HEIGHT: u32 = 16;
-
-const TICK_FREQUENCY: u64 = 60;
-
-static KEY_MAPPING: phf::Map<&'static str, u8> = phf_map! {
-    "1" => 0x1,
-    "2" => 0x2,
-    "3" => 0x3,
-    "4" => 0xC,
-    "Q" => 0x4,
-    "W" => 0x5,
-    "E" => 0x6,
-    "R" => 0xD,
-    "A" => 0x7,
-    "S" => 0x8,
-    "D" => 0x9,
-    "F" => 0xE,
-    "Z" => 0xA,
-    "Y" => 0xA,
-    "X" => 0x0,
-    "C" => 0xB,
-    "V" => 0xF
-};
+use chip8::cpu::CPU;
+use chip8::cpu::error::CPUError;
+use chip8::debug::Debugger;
+use chip8::io::{screenshot, Action, Audio, Clock, Display, Event, Input};
+use chip8::io::capture::{Capture, CaptureFormat, CaptureThread};
+use chip8::io::palette::Palette;
+use chip8::rewind::Rewind;
+use chip8::movie::{Movie, Player, Recorder};
+use chip8::scheduler::{Scheduler, DEFAULT_INSTRUCTIONS_PER_FRAME, FRAME_RATE,
+    MAX_INSTRUCTIONS_PER_FRAME, MIN_INSTRUCTIONS_PER_FRAME};
+
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufWriter, Write};
+use std::time::SystemTime;
+
+const STATE_SLOTS: u8 = 10;
+
+/// Number of frames that can be rewound, i.e. 30 seconds' worth.
+const REWIND_FRAMES: usize = 30 * FRAME_RATE as usize;
+
+const FAST_FORWARD_SPEED: f64 = 4.0;
+const SLOW_MOTION_SPEED: f64 = 0.25;
 
 pub struct Emulator {
-    canvas: sdl2::render::WindowCanvas,
-    event_pump: sdl2::EventPump,
-    cpu: chip8::cpu::CPU
+    display: Box<dyn Display>,
+    input: Box<dyn Input>,
+    audio: Box<dyn Audio>,
+    clock: Box<dyn Clock>,
+    cpu: CPU,
+    scheduler: Scheduler,
+    instructions_per_frame: u32,
+    /// Whether the fast-forward key is being held.
+    fast_forward: bool,
+    slow_motion: bool,
+    debugger: Debugger,
+    /// Save state slot that F5 and F8 save to and load from.
+    slot: u8,
+    rewind: Rewind,
+    /// Whether the rewind key is being held.
+    rewinding: bool,
+    /// Number of frames run since power-on.
+    frame: u32,
+    /// Where to write the movie being recorded.
+    record_path: Option<String>,
+    recorder: Option<Recorder>,
+    player: Option<Player>,
+    paused: bool,
+    /// Whether to enter the debugger before the first instruction.
+    break_on_start: bool,
+    /// Why the CPU stopped, if it crashed.
+    crash: Option<String>,
+    /// State of the CPU when the program was started, restored on reset.
+    initial_state: Vec<u8>,
+    image_palette: Palette,
+    /// Image pixels per lores pixel in screenshots and captures.
+    image_scale: u32,
+    /// Recording of the display started by the capture hotkey or `capture`.
+    capture: Option<(String, CaptureThread<BufWriter<File>>)>
 }
 
-fn time_now_ms() -> u64 {
-    Instant::now().elapsed().as_millis() as u64
+fn state_path(slot: u8) -> String {
+    format!("state{}.c8s", slot)
 }
 
 impl Emulator {
-    pub fn new() -> Emulator {
-        let sdl = sdl2::init().expect("Failed to initialise SDL2");
-        let video = sdl.video().expect("Failed to initialise SDL2 video subsystem");
+    /// Creates an emulator running `cpu`, which should have a program loaded.
+    pub fn new(display: Box<dyn Display>, input: Box<dyn Input>, audio: Box<dyn Audio>, clock: Box<dyn Clock>,
+        cpu: CPU) -> Emulator {
+        let scheduler = Scheduler::new(clock.now());
+
+        Emulator {
+            display,
+            input,
+            audio,
+            clock,
+            cpu,
+            scheduler,
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            fast_forward: false,
+            slow_motion: false,
+            debugger: Debugger::new(),
+            slot: 0,
+            rewind: Rewind::new(REWIND_FRAMES),
+            rewinding: false,
+            frame: 0,
+            record_path: None,
+            recorder: None,
+            player: None,
+            paused: false,
+            break_on_start: false,
+            crash: None,
+            initial_state: Vec::new(),
+            image_palette: Palette::default(),
+            image_scale: 1,
+            capture: None
+        }
+    }
+
+    pub fn set_instructions_per_frame(&mut self, instructions_per_frame: u32) {
+        self.instructions_per_frame = instructions_per_frame.clamp(MIN_INSTRUCTIONS_PER_FRAME, MAX_INSTRUCTIONS_PER_FRAME);
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.update_status();
+    }
+
+    pub fn set_break_on_start(&mut self, break_on_start: bool) {
+        self.break_on_start = break_on_start;
+    }
+
+    /// Sets the colours and size of screenshots and captures, in image
+    /// pixels per lores pixel.
+    pub fn set_image_style(&mut self, palette: Palette, scale: u32) {
+        self.image_palette = palette;
+        self.image_scale = scale;
+    }
+
+    fn update_status(&mut self) {
+        let status = match &self.crash {
+            Some(err) => Some(format!("crashed: {}", err)),
+            None if self.paused => Some("paused".to_string()),
+            None => None
+        };
+
+        self.display.set_status(status.as_deref());
+    }
+
+    fn toggle_pause(&mut self) {
+        self.set_paused(!self.paused);
+
+        // Don't try to catch up on the time spent paused.
+        if !self.paused {
+            self.scheduler.reset(self.clock.now());
+        }
+    }
+
+    fn update_speed(&mut self) {
+        let speed = if self.fast_forward {
+            FAST_FORWARD_SPEED
+        } else if self.slow_motion {
+            SLOW_MOTION_SPEED
+        } else {
+            1.0
+        };
+
+        self.scheduler.set_speed(speed);
+    }
+
+    fn crash(&mut self, err: CPUError) {
+        eprintln!("CPU crashed: {}", err);
+
+        self.crash = Some(err.to_string());
+        self.update_status();
+    }
+
+    /// Records the keypad input to a movie at `path` once the emulator quits.
+    pub fn record(&mut self, path: &str) {
+        self.record_path = Some(path.to_string());
+    }
+
+    /// Drives the keypad from `movie` instead of the keyboard.
+    pub fn play(&mut self, movie: Movie) {
+        self.player = Some(Player::new(movie));
+    }
 
-        let window = video.window(WINDOW_TITLE, 64 * PIXEL_WIDTH, 32 * PIXEL_HEIGHT)
-            .position_centered()
-            .build()
-            .expect("Failed to create window");
+    /// Save states and rewinding would desync a movie, so they're disabled
+    /// while one is recorded or played.
+    fn movie_active(&self) -> bool {
+        self.recorder.is_some() || self.player.is_some()
+    }
+
+    fn set_key(&mut self, key: u8, pressed: bool) {
+        if self.player.is_some() {
+            return;
+        }
+
+        self.cpu.env.keyboard[key as usize] = pressed;
+
+        if pressed {
+            self.cpu.press_key(key);
+        }
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(self.frame, &self.cpu.env.keyboard);
+        }
+    }
+
+    fn finish_recording(&mut self) {
+        if let (Some(recorder), Some(path)) = (self.recorder.take(), &self.record_path) {
+            let movie = recorder.finish(self.frame);
+
+            match fs::write(path, movie.to_string()) {
+                Ok(()) => println!("recorded {} frames to {}", movie.length, path),
+                Err(err) => eprintln!("Failed to write movie to {}: {}", path, err)
+            }
+        }
+    }
+
+    /// Clears a crash after the CPU state has been replaced.
+    fn recover(&mut self) {
+        if self.crash.is_some() {
+            self.crash = None;
+            self.update_status();
+        }
+    }
+
+    fn save_state(&self) {
+        let path = state_path(self.slot);
+
+        match fs::write(&path, self.cpu.save_state()) {
+            Ok(()) => println!("saved state to {}", path),
+            Err(err) => eprintln!("Failed to save state to {}: {}", path, err)
+        }
+    }
+
+    fn load_state(&mut self) {
+        if self.movie_active() {
+            eprintln!("Save states can't be loaded while a movie is recorded or played");
+            return;
+        }
+
+        let path = state_path(self.slot);
 
-        let canvas: sdl2::render::WindowCanvas = window.into_canvas()
-            .present_vsync()
-            .build().expect("Failed to create canvas for window");
+        match fs::read(&path) {
+            Ok(data) => match self.cpu.load_state(&data) {
+                Ok(()) => {
+                    println!("loaded state from {}", path);
+                    self.recover();
+                },
+                Err(err) => eprintln!("Failed to load state from {}: {}", path, err)
+            },
+            Err(err) => eprintln!("Failed to read {}: {}", path, err)
+        }
+    }
+
+    /// Restarts the program from the state it was started in.
+    fn reset(&mut self) {
+        if self.movie_active() {
+            eprintln!("The emulator can't be reset while a movie is recorded or played");
+            return;
+        }
 
-        let event_pump = sdl.event_pump().expect("Failed to initialise SDL2 event subsystem");
+        self.cpu.load_state(&self.initial_state).expect("Failed to restore initial state");
+        self.frame = 0;
+        self.rewind.clear();
+        self.recover();
+        println!("reset");
+    }
+
+    fn screenshot(&self) {
+        let path = screenshot::file_name(SystemTime::now());
 
-        Emulator { canvas, event_pump, cpu: chip8::cpu::CPU::new() }
+        match fs::write(&path, screenshot::image_png(&self.cpu.env, &self.image_palette, self.image_scale)) {
+            Ok(()) => println!("saved screenshot to {}", path),
+            Err(err) => eprintln!("Failed to save screenshot to {}: {}", path, err)
+        }
     }
 
-    fn draw_screen(&mut self) {
-        self.canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
-        self.canvas.clear();
+    /// Starts recording every frame to `path`, as a GIF if it ends in `.gif`
+    /// or as raw RGB24 frames otherwise, at the same size as screenshots.
+    pub fn capture(&mut self, path: &str) -> io::Result<()> {
+        self.stop_capture();
 
-        let w = self.cpu.env.display_width as u32;
-        let h = self.cpu.env.display_height as u32;
-        let disp = &self.cpu.env.display;
+        let file = BufWriter::new(File::create(path)?);
+        let format = CaptureFormat::from_path(path);
+        let capture = CaptureThread::spawn(Capture::new(file, format, self.image_palette, self.image_scale)?);
 
-        self.canvas.set_draw_color(sdl2::pixels::Color::RGB(255, 255, 255));
+        let (width, height) = capture.size();
+        println!("capturing {}x{} frames to {}", width, height, path);
+        self.capture = Some((path.to_string(), capture));
 
-        for x in 0..w {
-            for y in 0..h {
-                if disp[(y * w + x) as usize] {
-                    let rect = sdl2::rect::Rect::new(
-                        (x * PIXEL_WIDTH) as i32,
-                        (y * PIXEL_HEIGHT) as i32,
-                        PIXEL_WIDTH,
-                        PIXEL_HEIGHT
-                    );
+        Ok(())
+    }
 
-                    self.canvas.fill_rect(rect).expect("Failed to draw rectangle");
+    fn stop_capture(&mut self) {
+        if let Some((path, capture)) = self.capture.take() {
+            let frames = capture.frames();
+
+            match capture.finish() {
+                Ok(_) => println!("captured {} frames to {}", frames, path),
+                Err(err) => eprintln!("Failed to write capture to {}: {}", path, err)
+            }
+        }
+    }
+
+    fn toggle_capture(&mut self) {
+        if self.capture.is_some() {
+            self.stop_capture();
+        } else {
+            let path = screenshot::timestamped_file_name("capture", "gif", SystemTime::now());
+
+            if let Err(err) = self.capture(&path) {
+                eprintln!("Failed to start capture to {}: {}", path, err);
+            }
+        }
+    }
+
+    fn capture_frame(&mut self) {
+        if let Some((_, capture)) = &mut self.capture {
+            // The capture thread only stops early on errors, which finishing reports.
+            if capture.add_frame(&self.cpu.env).is_err() {
+                self.stop_capture();
+            }
+        }
+    }
+
+    fn select_slot(&mut self, slot: u8) {
+        self.slot = slot % STATE_SLOTS;
+        println!("selected save state slot {}", self.slot);
+    }
+
+    fn present(&mut self) {
+        self.display.show_registers(&self.cpu);
+        self.display.present(&self.cpu.env);
+    }
+
+    /// Reads debugger commands from stdin until execution is resumed.
+    fn debug_repl(&mut self) {
+        let stdin = io::stdin();
+        let mut line = String::new();
+
+        // Execution stops while the REPL waits for input, so the buzzer would drone on.
+        self.audio.update(0, &self.cpu.env);
+        self.display.set_debugging(true);
+        println!("{}", chip8::debug::format_registers(&self.cpu));
+
+        loop {
+            print!("(chip8) ");
+            io::stdout().flush().expect("Failed to flush stdout");
+
+            line.clear();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                self.debugger.resume(&self.cpu);
+                break;
+            }
+
+            let response = self.debugger.command(&mut self.cpu, &line);
+
+            if !response.text.is_empty() {
+                println!("{}", response.text);
+            }
+
+            self.present();
+
+            if response.resume {
+                break;
+            }
+        }
+
+        self.display.set_debugging(false);
+
+        // Don't try to catch up on the time spent paused.
+        self.scheduler.reset(self.clock.now());
+    }
+
+    /// Runs one frame: up to `instructions_per_frame` instructions followed
+    /// by a timer tick.
+    fn run_frame(&mut self) {
+        if self.crash.is_some() {
+            return;
+        }
+
+        if let Some(player) = &mut self.player {
+            if player.is_finished(self.frame) {
+                println!("movie finished after {} frames", self.frame);
+                self.player = None;
+            } else {
+                player.apply(self.frame, &mut self.cpu);
+            }
+        }
+
+        for _ in 0..self.instructions_per_frame {
+            if let Some(stop) = self.debugger.check(&self.cpu) {
+                println!("{}", stop);
+                self.debug_repl();
+            }
+
+            match self.cpu.step() {
+                Ok(true) => (),
+                Ok(false) => break,
+                Err(err) => {
+                    self.crash(err);
+                    return;
                 }
             }
         }
+
+        self.cpu.tick();
+
+        if !self.movie_active() {
+            self.rewind.push(self.cpu.save_state());
+        }
+
+        self.frame = self.frame.wrapping_add(1);
     }
 
+    fn rewind_frame(&mut self) {
+        if let Some(state) = self.rewind.pop() {
+            self.cpu.load_state(&state).expect("Failed to restore rewind state");
+            self.recover();
+        }
+    }
+
+    /// Runs until the window is closed. `program` is the ROM loaded into the
+    /// CPU, which movies are checked against.
     pub fn start(&mut self, program: &[u8]) {
-        self.cpu.mem.load_program(program);
+        self.initial_state = self.cpu.save_state();
 
-        let mut last_time = time_now_ms();
-        let mut tick_timer = 0;
+        if let Some(player) = &self.player {
+            let movie = player.movie();
 
-        'main_loop: loop {
-            let now = time_now_ms();
-            let dt = if last_time > now { now } else { now - last_time };
-            last_time = now;
+            if movie.rom_hash != chip8::movie::rom_hash(program) {
+                eprintln!("Warning: the movie was recorded with a different ROM");
+            }
+
+            self.cpu.reseed(movie.seed);
+            self.instructions_per_frame = movie.instructions_per_frame;
+        } else if self.record_path.is_some() {
+            self.recorder = Some(Recorder::new(self.cpu.seed(), program, self.instructions_per_frame));
+        }
+
+        if self.break_on_start {
+            self.present();
+            self.debug_repl();
+        }
 
-            tick_timer += dt;
+        self.scheduler.reset(self.clock.now());
 
-            for event in self.event_pump.poll_iter() {
+        'main_loop: loop {
+            for event in self.input.poll() {
                 match event {
-                    Event::Quit {..} => break 'main_loop,
-                    Event::KeyDown { keycode, .. } => {
-                        match keycode.and_then(|k| KEY_MAPPING.get(k.name().as_str())) {
-                            Some(key) => {
-                                self.cpu.env.keyboard[*key as usize] = true;
-                                self.cpu.press_key(*key)
-                            },
-                            None => {}
-                        }
+                    Event::Quit => break 'main_loop,
+                    Event::Key { key, pressed } => self.set_key(key, pressed),
+                    Event::Action { action: Action::Rewind, pressed } => self.rewinding = pressed && !self.movie_active(),
+                    Event::Action { action: Action::FastForward, pressed } => {
+                        self.fast_forward = pressed;
+                        self.update_speed();
                     },
-                    Event::KeyUp { keycode, .. } => {
-                        match keycode.and_then(|k| KEY_MAPPING.get(k.name().as_str())) {
-                            Some(key) => self.cpu.env.keyboard[*key as usize] = false,
-                            None => {}
-                        }
-                    }
-                    _ => {}
+                    Event::Action { action, pressed: true } => match action {
+                        Action::SaveState => self.save_state(),
+                        Action::LoadState => self.load_state(),
+                        Action::PreviousSlot => self.select_slot(self.slot + STATE_SLOTS - 1),
+                        Action::NextSlot => self.select_slot(self.slot + 1),
+                        Action::SlowMotion => {
+                            self.slow_motion = !self.slow_motion;
+                            self.update_speed();
+                        },
+                        Action::Pause => self.toggle_pause(),
+                        Action::Reset => self.reset(),
+                        Action::Screenshot => self.screenshot(),
+                        Action::Capture => self.toggle_capture(),
+                        Action::Fullscreen => self.display.toggle_fullscreen(),
+                        Action::Debug => {
+                            println!("paused");
+                            self.debug_repl();
+                        },
+                        Action::Rewind | Action::FastForward => ()
+                    },
+                    Event::Action { pressed: false, .. } => ()
                 }
             }
 
-            if tick_timer >= 1000 / TICK_FREQUENCY {
-                self.cpu.tick();
-                tick_timer = 0;
+            let frames = self.scheduler.frames_due(self.clock.now());
+
+            if !self.paused {
+                for _ in 0..frames {
+                    if self.rewinding {
+                        self.rewind_frame();
+                    } else {
+                        self.run_frame();
+                    }
+
+                    self.capture_frame();
+                }
             }
 
-            self.cpu.step();
-            self.draw_screen();
+            if frames > 0 {
+                let sound_timer = if self.paused { 0 } else { self.cpu.regs.st };
 
-            self.canvas.present();
+                self.audio.update(sound_timer, &self.cpu.env);
+                self.present();
+            }
+
+            let wait = self.scheduler.time_until_next_frame(self.clock.now());
+            self.clock.sleep(wait);
         }
+
+        self.finish_recording();
+        self.stop_capture();
     }
 }
\ No newline at end of file
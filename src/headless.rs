@@ -0,0 +1,234 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Write;
+
+use super::cpu::{CPU, CPUEnvironment, Interrupt};
+use super::cpu::error::CPUError;
+use super::io::{screenshot, Audio, Display, NullAudio, NullDisplay};
+use super::io::palette::Palette;
+use super::movie::{set_keys, InputEvent};
+use super::scheduler::DEFAULT_INSTRUCTIONS_PER_FRAME;
+
+/// When a headless run stops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Frames(u32),
+    Instructions(u64)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub limit: Limit,
+    pub instructions_per_frame: u32,
+    /// Keypad changes to apply, ordered by frame.
+    pub input: Vec<InputEvent>
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            limit: Limit::Frames(60),
+            instructions_per_frame: DEFAULT_INSTRUCTIONS_PER_FRAME,
+            input: Vec::new()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    pub frames: u32,
+    pub instructions: u64,
+    /// Whether the program ran `EXIT`.
+    pub exited: bool,
+    /// Whether an instruction limit couldn't be reached because the program
+    /// waited for a key that no scripted input was left to press.
+    pub blocked: bool,
+    pub error: Option<CPUError>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ScriptError {}
+
+/// Parses an input script. Each line holds a frame number followed by keys
+/// to press (`+5`) or release (`-5`) at the start of that frame. Everything
+/// after a `#` is a comment.
+///
+/// ```text
+/// 30 +5      # hold 5
+/// 45 -5 +A
+/// ```
+pub fn parse_script(text: &str) -> Result<Vec<InputEvent>, ScriptError> {
+    let mut events: Vec<InputEvent> = Vec::new();
+    let mut keys = 0u16;
+
+    for (i, line) in text.lines().enumerate() {
+        let error = |message: String| Err(ScriptError { line: i + 1, message });
+        let mut words = line.split('#').next().unwrap_or("").split_whitespace();
+
+        let frame = match words.next() {
+            Some(frame) => match frame.parse() {
+                Ok(frame) => frame,
+                Err(_) => return error(format!("invalid frame number `{}`", frame))
+            },
+            None => continue
+        };
+
+        if matches!(events.last(), Some(last) if last.frame > frame) {
+            return error("frames are out of order".to_string());
+        }
+
+        for word in words {
+            let mut chars = word.chars();
+            let sign = chars.next();
+
+            let key = match u8::from_str_radix(chars.as_str(), 16) {
+                Ok(key) if key < 16 => 1 << key,
+                _ => return error(format!("invalid key `{}`", word))
+            };
+
+            match sign {
+                Some('+') => keys |= key,
+                Some('-') => keys &= !key,
+                _ => return error(format!("expected `+` or `-` before key `{}`", word))
+            }
+        }
+
+        events.push(InputEvent { frame, keys });
+    }
+
+    Ok(events)
+}
+
+/// Runs the CPU without any frontend. Each frame executes up to
+/// `instructions_per_frame` instructions and then ticks the timers.
+pub fn run(cpu: &mut CPU, options: &Options) -> Report {
+    run_with(cpu, options, &mut NullDisplay, &mut NullAudio)
+}
+
+/// Like `run`, but presents every frame to `display` and `audio`, without
+/// any pacing.
+pub fn run_with(cpu: &mut CPU, options: &Options, display: &mut dyn Display, audio: &mut dyn Audio) -> Report {
+    let mut report = Report { frames: 0, instructions: 0, exited: false, blocked: false, error: None };
+    let mut input = options.input.iter().peekable();
+
+    let done = |report: &Report| match options.limit {
+        Limit::Frames(frames) => report.frames >= frames,
+        Limit::Instructions(instructions) => report.instructions >= instructions
+    };
+
+    while !done(&report) {
+        while let Some(event) = input.next_if(|event| event.frame <= report.frames) {
+            set_keys(cpu, event.keys);
+        }
+
+        for _ in 0..options.instructions_per_frame {
+            if done(&report) {
+                break;
+            }
+
+            match cpu.step() {
+                Ok(true) => report.instructions += 1,
+                Ok(false) => break,
+                Err(err) => {
+                    report.error = Some(err);
+                    return report;
+                }
+            }
+        }
+
+        match cpu.interrupt {
+            Interrupt::Exit => {
+                report.exited = true;
+                return report;
+            },
+            // Frame limits are reached regardless, but without input the
+            // instruction count would never grow again.
+            Interrupt::AwaitKey(_) if input.peek().is_none() && matches!(options.limit, Limit::Instructions(_)) => {
+                report.blocked = true;
+                return report;
+            },
+            _ => ()
+        }
+
+        cpu.tick();
+        report.frames += 1;
+
+        audio.update(cpu.regs.st, &cpu.env);
+        display.present(&cpu.env);
+    }
+
+    report
+}
+
+/// Renders the display as text, like `screenshot::text`.
+pub fn display_text(env: &CPUEnvironment) -> String {
+    screenshot::text(env)
+}
+
+/// Renders the display as a black and white PNG, one image pixel per CHIP-8
+/// pixel. Pixels lit on any plane are white.
+pub fn display_png(env: &CPUEnvironment) -> Vec<u8> {
+    let white = (0xFF, 0xFF, 0xFF);
+    screenshot::png(env, &Palette { colours: [(0, 0, 0), white, white, white] }, 1)
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); },
+            c => out.push(c)
+        }
+    }
+
+    out.push('"');
+    out
+}
+
+fn json_list<T: fmt::Display>(items: impl Iterator<Item = T>) -> String {
+    let items: Vec<String> = items.map(|item| item.to_string()).collect();
+    format!("[{}]", items.join(", "))
+}
+
+/// Describes the registers and the outcome of a run as a JSON object.
+pub fn registers_json(cpu: &CPU, report: &Report) -> String {
+    let error = match report.error {
+        Some(err) => json_string(&err.to_string()),
+        None => "null".to_string()
+    };
+
+    let fields = [
+        ("pc", cpu.regs.pc.to_string()),
+        ("i", cpu.regs.i.to_string()),
+        ("dt", cpu.regs.dt.to_string()),
+        ("st", cpu.regs.st.to_string()),
+        ("v", json_list((0..16).map(|x| cpu.regs.v(x).unwrap_or(0)))),
+        ("stack", json_list(cpu.stack().iter())),
+        ("seed", json_string(&format!("{:016x}", cpu.seed()))),
+        ("frames", report.frames.to_string()),
+        ("instructions", report.instructions.to_string()),
+        ("exited", report.exited.to_string()),
+        ("blocked", report.blocked.to_string()),
+        ("error", error)
+    ];
+
+    let fields: Vec<String> = fields.iter()
+        .map(|(name, value)| format!("  {}: {}", json_string(name), value))
+        .collect();
+
+    format!("{{\n{}\n}}\n", fields.join(",\n"))
+}
@@ -0,0 +1,214 @@
+use std::io;
+use std::io::Write;
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use super::Display;
+use super::gif::GifEncoder;
+use super::palette::Palette;
+use super::screenshot;
+use super::super::cpu::CPUEnvironment;
+use super::super::scheduler::FRAME_RATE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureFormat {
+    Gif,
+    /// RGB24 frames one after another, without any header.
+    Raw
+}
+
+impl CaptureFormat {
+    /// GIF for paths ending in `.gif`, raw frames otherwise.
+    pub fn from_path(path: &str) -> CaptureFormat {
+        if path.to_ascii_lowercase().ends_with(".gif") {
+            CaptureFormat::Gif
+        } else {
+            CaptureFormat::Raw
+        }
+    }
+}
+
+enum Encoder<W: Write> {
+    Gif(GifEncoder<W>),
+    Raw(W)
+}
+
+/// Time frame `frame` starts at, in the hundredths of a second GIF delays
+/// are given in.
+fn frame_time(frame: u64) -> u64 {
+    (frame * 100 + FRAME_RATE as u64 / 2) / FRAME_RATE as u64
+}
+
+/// Records frames of the display, one per `add_frame` or `present` call,
+/// either as an animated GIF or as raw RGB24 video, which e.g. ffmpeg can
+/// convert with
+/// `ffmpeg -f rawvideo -pix_fmt rgb24 -s <width>x<height> -r 60 -i <file> out.mp4`.
+/// Frames are the same size as screenshots at the same scale, see
+/// `screenshot::image_size`.
+pub struct Capture<W: Write> {
+    encoder: Encoder<W>,
+    palette: Palette,
+    width: u32,
+    height: u32,
+    frames: u64,
+    /// The last GIF frame and the frame it started on, held back so that
+    /// repeats of it just lengthen its delay.
+    pending: Option<(Vec<u8>, u64)>,
+    /// The first error from `present`, which can't return it.
+    error: Option<io::Error>
+}
+
+impl<W: Write> Capture<W> {
+    /// Starts a capture writing to `writer`, with each lores pixel `scale`
+    /// image pixels wide and high.
+    pub fn new(writer: W, format: CaptureFormat, palette: Palette, scale: u32) -> io::Result<Capture<W>> {
+        let (width, height) = screenshot::image_size(scale);
+
+        let encoder = match format {
+            CaptureFormat::Gif => {
+                if width > u16::MAX as u32 || height > u16::MAX as u32 {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, "capture is too large for a GIF"));
+                }
+
+                Encoder::Gif(GifEncoder::new(writer, width as u16, height as u16, &palette.colours)?)
+            },
+            CaptureFormat::Raw => Encoder::Raw(writer)
+        };
+
+        Ok(Capture { encoder, palette, width, height, frames: 0, pending: None, error: None })
+    }
+
+    /// Size of the frames in pixels.
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Number of frames captured so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        if let (Encoder::Gif(gif), Some((indices, start))) = (&mut self.encoder, self.pending.take()) {
+            let delay = (frame_time(self.frames) - frame_time(start)).min(u16::MAX as u64);
+            gif.add_frame(&indices, delay as u16)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn add_frame(&mut self, env: &CPUEnvironment) -> io::Result<()> {
+        self.add_display(&env.display, env.display_width as usize)
+    }
+
+    /// Adds a frame of the colours in `display`, `display_width` pixels wide.
+    pub fn add_display(&mut self, display: &[u8], display_width: usize) -> io::Result<()> {
+        let indices = screenshot::resize(display, display_width, self.width, self.height);
+
+        match &mut self.encoder {
+            Encoder::Gif(_) => {
+                if self.pending.as_ref().map(|(pending, _)| pending) != Some(&indices) {
+                    self.write_pending()?;
+                    self.pending = Some((indices, self.frames));
+                }
+            },
+            Encoder::Raw(writer) => {
+                let mut rgb = Vec::with_capacity(indices.len() * 3);
+
+                for index in indices {
+                    let (r, g, b) = self.palette.colour(index);
+                    rgb.extend_from_slice(&[r, g, b]);
+                }
+
+                writer.write_all(&rgb)?;
+            }
+        }
+
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Writes the rest of the capture, returning the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        if let Some(err) = self.error.take() {
+            return Err(err);
+        }
+
+        self.write_pending()?;
+
+        match self.encoder {
+            Encoder::Gif(gif) => gif.finish(),
+            Encoder::Raw(mut writer) => {
+                writer.flush()?;
+                Ok(writer)
+            }
+        }
+    }
+}
+
+impl<W: Write> Display for Capture<W> {
+    fn present(&mut self, env: &CPUEnvironment) {
+        if self.error.is_none() {
+            if let Err(err) = self.add_frame(env) {
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+/// Frames that can wait to be encoded before the emulator has to, i.e. 5
+/// seconds' worth.
+const QUEUED_FRAMES: usize = 5 * FRAME_RATE as usize;
+
+/// Runs a `Capture` on its own thread, so that encoding frames doesn't hold
+/// up emulation.
+pub struct CaptureThread<W: Write + Send + 'static> {
+    sender: SyncSender<(Vec<u8>, usize)>,
+    thread: JoinHandle<io::Result<W>>,
+    size: (u32, u32),
+    frames: u64
+}
+
+impl<W: Write + Send + 'static> CaptureThread<W> {
+    pub fn spawn(mut capture: Capture<W>) -> CaptureThread<W> {
+        let size = capture.size();
+        let (sender, receiver) = mpsc::sync_channel::<(Vec<u8>, usize)>(QUEUED_FRAMES);
+
+        let thread = thread::spawn(move || {
+            for (display, display_width) in receiver {
+                capture.add_display(&display, display_width)?;
+            }
+
+            capture.finish()
+        });
+
+        CaptureThread { sender, thread, size, frames: 0 }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Number of frames captured so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    /// Queues a frame of the display. Fails if the capture has already
+    /// failed, which `finish` then reports.
+    pub fn add_frame(&mut self, env: &CPUEnvironment) -> io::Result<()> {
+        self.sender.send((env.display.clone(), env.display_width as usize))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "capture stopped"))?;
+
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// Encodes the queued frames and writes the rest of the capture,
+    /// returning the writer.
+    pub fn finish(self) -> io::Result<W> {
+        drop(self.sender);
+
+        self.thread.join().unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::BrokenPipe, "capture thread panicked")))
+    }
+}
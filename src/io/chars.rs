@@ -94,4 +94,182 @@ pub const CHIP8_CHARACTERS: [u8; 5 * 16] = [
     0b11110000,
     0b10000000,
     0b10000000
+];
+
+pub const SCHIP8_CHARACTERS: [u8; 10 * 16] = [
+    0b00111100,
+    0b01111110,
+    0b11100111,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11100111,
+    0b01111110,
+    0b00111100,
+
+    0b00011000,
+    0b00111000,
+    0b01011000,
+    0b00011000,
+    0b00011000,
+    0b00011000,
+    0b00011000,
+    0b00011000,
+    0b00011000,
+    0b00111100,
+
+    0b00111110,
+    0b01111111,
+    0b11000011,
+    0b00000110,
+    0b00001100,
+    0b00011000,
+    0b00110000,
+    0b01100000,
+    0b11111111,
+    0b11111111,
+
+    0b00111100,
+    0b01111110,
+    0b11000011,
+    0b00000011,
+    0b00001110,
+    0b00001110,
+    0b00000011,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+
+    0b00000110,
+    0b00001110,
+    0b00011110,
+    0b00110110,
+    0b01100110,
+    0b11000110,
+    0b11111111,
+    0b11111111,
+    0b00000110,
+    0b00000110,
+
+    0b11111111,
+    0b11111111,
+    0b11000000,
+    0b11000000,
+    0b11111100,
+    0b11111110,
+    0b00000011,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+
+    0b00111110,
+    0b01111100,
+    0b11100000,
+    0b11000000,
+    0b11111100,
+    0b11111110,
+    0b11000011,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+
+    0b11111111,
+    0b11111111,
+    0b00000011,
+    0b00000110,
+    0b00001100,
+    0b00011000,
+    0b00110000,
+    0b01100000,
+    0b01100000,
+    0b01100000,
+
+    0b00111100,
+    0b01111110,
+    0b11000011,
+    0b11000011,
+    0b01111110,
+    0b01111110,
+    0b11000011,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+
+    0b00111100,
+    0b01111110,
+    0b11000011,
+    0b11000011,
+    0b01111111,
+    0b00111111,
+    0b00000011,
+    0b00000011,
+    0b00111110,
+    0b01111100,
+
+    0b00011000,
+    0b00111100,
+    0b01100110,
+    0b11000011,
+    0b11000011,
+    0b11111111,
+    0b11111111,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+
+    0b11111100,
+    0b11111110,
+    0b11000011,
+    0b11000011,
+    0b11111110,
+    0b11111110,
+    0b11000011,
+    0b11000011,
+    0b11111110,
+    0b11111100,
+
+    0b00111100,
+    0b01111110,
+    0b11000011,
+    0b11000000,
+    0b11000000,
+    0b11000000,
+    0b11000000,
+    0b11000011,
+    0b01111110,
+    0b00111100,
+
+    0b11111100,
+    0b11111110,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11000011,
+    0b11111110,
+    0b11111100,
+
+    0b11111111,
+    0b11111111,
+    0b11000000,
+    0b11000000,
+    0b11111100,
+    0b11111100,
+    0b11000000,
+    0b11000000,
+    0b11111111,
+    0b11111111,
+
+    0b11111111,
+    0b11111111,
+    0b11000000,
+    0b11000000,
+    0b11111100,
+    0b11111100,
+    0b11000000,
+    0b11000000,
+    0b11000000,
+    0b11000000
 ];
\ No newline at end of file
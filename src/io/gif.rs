@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+
+use super::palette::Rgb;
+
+/// Bits per pixel of the 4-colour images written.
+const MIN_CODE_SIZE: u8 = 2;
+const MAX_CODE_SIZE: u8 = 12;
+
+/// Packs variable-width codes least significant bit first.
+struct BitWriter {
+    out: Vec<u8>,
+    bits: u32,
+    len: u8
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.bits |= (code as u32) << self.len;
+        self.len += size;
+
+        while self.len >= 8 {
+            self.out.push(self.bits as u8);
+            self.bits >>= 8;
+            self.len -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.len > 0 {
+            self.out.push(self.bits as u8);
+        }
+
+        self.out
+    }
+}
+
+/// Compresses colour indices with GIF's variant of LZW.
+fn lzw_encode(indices: &[u8]) -> Vec<u8> {
+    let clear = 1u16 << MIN_CODE_SIZE;
+    let end = clear + 1;
+
+    let mut out = BitWriter { out: Vec::new(), bits: 0, len: 0 };
+    let mut dictionary: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut code_size = MIN_CODE_SIZE + 1;
+    let mut next = end + 1;
+    let mut prefix: Option<u16> = None;
+
+    out.write(clear, code_size);
+
+    for &index in indices {
+        let p = match prefix {
+            Some(p) => p,
+            None => {
+                prefix = Some(index as u16);
+                continue;
+            }
+        };
+
+        if let Some(&code) = dictionary.get(&(p, index)) {
+            prefix = Some(code);
+            continue;
+        }
+
+        out.write(p, code_size);
+
+        if next < 1 << MAX_CODE_SIZE {
+            dictionary.insert((p, index), next);
+
+            // The decoder adds each code one step later, so it widens its
+            // codes just as it reads the first one that needs it.
+            if next == 1 << code_size {
+                code_size += 1;
+            }
+
+            next += 1;
+        } else {
+            out.write(clear, code_size);
+            dictionary.clear();
+            code_size = MIN_CODE_SIZE + 1;
+            next = end + 1;
+        }
+
+        prefix = Some(index as u16);
+    }
+
+    if let Some(p) = prefix {
+        out.write(p, code_size);
+
+        // Reading the last code makes the decoder add the entry this encoder
+        // added last, which may widen the end code.
+        if next == 1 << code_size && code_size < MAX_CODE_SIZE {
+            code_size += 1;
+        }
+    }
+
+    out.write(end, code_size);
+    out.finish()
+}
+
+/// Writes an endlessly looping animation of images of up to 4 colours,
+/// frame by frame.
+pub struct GifEncoder<W: Write> {
+    writer: W,
+    width: u16,
+    height: u16
+}
+
+impl<W: Write> GifEncoder<W> {
+    pub fn new(mut writer: W, width: u16, height: u16, palette: &[Rgb; 4]) -> io::Result<GifEncoder<W>> {
+        writer.write_all(b"GIF89a")?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        // A global colour table of 4 entries, with 8 bits per primary colour.
+        writer.write_all(&[0xF1, 0, 0])?;
+
+        for (r, g, b) in palette {
+            writer.write_all(&[*r, *g, *b])?;
+        }
+
+        // Netscape application extension, looping forever.
+        writer.write_all(&[0x21, 0xFF, 0x0B])?;
+        writer.write_all(b"NETSCAPE2.0")?;
+        writer.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])?;
+
+        Ok(GifEncoder { writer, width, height })
+    }
+
+    /// Adds a frame of `width * height` colour indices, shown for `delay`
+    /// hundredths of a second.
+    pub fn add_frame(&mut self, indices: &[u8], delay: u16) -> io::Result<()> {
+        assert_eq!(self.width as usize * self.height as usize, indices.len(), "Frame has the wrong size");
+
+        let [delay_lo, delay_hi] = delay.to_le_bytes();
+        self.writer.write_all(&[0x21, 0xF9, 0x04, 0x00, delay_lo, delay_hi, 0x00, 0x00])?;
+
+        self.writer.write_all(&[0x2C, 0, 0, 0, 0])?;
+        self.writer.write_all(&self.width.to_le_bytes())?;
+        self.writer.write_all(&self.height.to_le_bytes())?;
+        self.writer.write_all(&[0x00, MIN_CODE_SIZE])?;
+
+        for block in lzw_encode(indices).chunks(255) {
+            self.writer.write_all(&[block.len() as u8])?;
+            self.writer.write_all(block)?;
+        }
+
+        self.writer.write_all(&[0x00])
+    }
+
+    /// Ends the animation, returning the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.write_all(&[0x3B])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use super::Action;
+
+/// Kind of input device a host key belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Device {
+    Keyboard,
+    Gamepad
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Binding {
+    /// A CHIP-8 keypad key.
+    Key(u8),
+    Action(Action)
+}
+
+const ACTION_NAMES: [(&str, Action); 13] = [
+    ("save_state", Action::SaveState),
+    ("load_state", Action::LoadState),
+    ("next_slot", Action::NextSlot),
+    ("previous_slot", Action::PreviousSlot),
+    ("rewind", Action::Rewind),
+    ("fast_forward", Action::FastForward),
+    ("slow_motion", Action::SlowMotion),
+    ("pause", Action::Pause),
+    ("reset", Action::Reset),
+    ("screenshot", Action::Screenshot),
+    ("capture", Action::Capture),
+    ("fullscreen", Action::Fullscreen),
+    ("debug", Action::Debug)
+];
+
+const DEFAULT_BINDINGS: [(&str, &str); 29] = [
+    ("1", "1"), ("2", "2"), ("3", "3"), ("C", "4"),
+    ("4", "Q"), ("5", "W"), ("6", "E"), ("D", "R"),
+    ("7", "A"), ("8", "S"), ("9", "D"), ("E", "F"),
+    // Z and Y are swapped on QWERTZ keyboards.
+    ("A", "Z, Y"), ("0", "X"), ("B", "C"), ("F", "V"),
+    ("save_state", "F5"),
+    ("load_state", "F8"),
+    ("next_slot", "F7"),
+    ("previous_slot", "F6"),
+    ("rewind", "Backspace"),
+    ("fast_forward", "Tab"),
+    ("slow_motion", "F2"),
+    ("pause", "P, Pause"),
+    ("reset", "F3"),
+    ("screenshot", "F9"),
+    ("capture", "F10"),
+    ("fullscreen", "F11"),
+    ("debug", "F12")
+];
+
+/// The d-pad and left stick drive the 2/4/6/8 keys most games move with,
+/// and A the 5 key which many use to fire.
+const DEFAULT_GAMEPAD_BINDINGS: [(&str, &str); 13] = [
+    ("2", "dpup, lefty-"),
+    ("8", "dpdown, lefty+"),
+    ("4", "dpleft, leftx-"),
+    ("6", "dpright, leftx+"),
+    ("5", "a"),
+    ("0", "b"),
+    ("A", "x"),
+    ("B", "y"),
+    ("pause", "start"),
+    ("reset", "back"),
+    ("rewind", "leftshoulder"),
+    ("fast_forward", "rightshoulder"),
+    ("slow_motion", "lefttrigger+")
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeymapError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.message)
+    }
+}
+
+impl Error for KeymapError {}
+
+impl Binding {
+    /// Parses a CHIP-8 key's hex digit or a hotkey name.
+    pub fn from_name(name: &str) -> Option<Binding> {
+        if name.len() == 1 {
+            return u8::from_str_radix(name, 16).ok().map(Binding::Key);
+        }
+
+        ACTION_NAMES.iter()
+            .find(|(action_name, _)| action_name.eq_ignore_ascii_case(name))
+            .map(|(_, action)| Binding::Action(*action))
+    }
+}
+
+/// What the bindings of a config file section apply to.
+enum Section {
+    Bindings(Device),
+    /// Overrides for the ROM being run.
+    Override(Device),
+    /// Overrides for a different ROM.
+    Ignored
+}
+
+fn split_keys(keys: &str) -> Vec<String> {
+    keys.split(',').map(str::trim).filter(|key| !key.is_empty()).map(str::to_ascii_lowercase).collect()
+}
+
+/// Maps host key and button names to bindings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: HashMap<(Device, String), Binding>
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let mut keymap = Keymap::empty();
+
+        let defaults = DEFAULT_BINDINGS.iter().map(|binding| (Device::Keyboard, binding))
+            .chain(DEFAULT_GAMEPAD_BINDINGS.iter().map(|binding| (Device::Gamepad, binding)));
+
+        for (device, (name, keys)) in defaults {
+            let binding = Binding::from_name(name).expect("Invalid default binding");
+            keymap.bind(device, binding, &split_keys(keys));
+        }
+
+        keymap
+    }
+}
+
+fn parse_section(header: &str, rom: Option<&str>) -> Option<Section> {
+    let (device, header) = match header.strip_prefix("gamepad") {
+        Some(rest) => (Device::Gamepad, rest.trim_start()),
+        None => (Device::Keyboard, header)
+    };
+
+    if let Some(name) = header.strip_prefix("rom ") {
+        return Some(if rom == Some(name.trim()) { Section::Override(device) } else { Section::Ignored });
+    }
+
+    match (device, header) {
+        (Device::Keyboard, "keys") | (Device::Gamepad, "") => Some(Section::Bindings(device)),
+        _ => None
+    }
+}
+
+impl Keymap {
+    pub fn empty() -> Keymap {
+        Keymap { bindings: HashMap::new() }
+    }
+
+    /// Parses a config file on top of the default bindings, applying the
+    /// overrides for `rom` after the `[keys]` and `[gamepad]` sections:
+    ///
+    /// ```text
+    /// # CHIP-8 keys are bound by their hex digit, hotkeys by name.
+    /// [keys]
+    /// 5 = W, Up
+    /// pause = P
+    ///
+    /// # Overrides for ROMs with the given file name.
+    /// [rom pong.ch8]
+    /// 1 = Up
+    /// 4 = Down
+    ///
+    /// # Game controller bindings, which can be overridden the same way.
+    /// [gamepad]
+    /// 5 = a, rightshoulder
+    /// rewind = lefttrigger+
+    ///
+    /// [gamepad rom pong.ch8]
+    /// 1 = dpup, lefty-
+    /// ```
+    ///
+    /// Keyboard keys are named as in SDL, e.g. `A`, `Left Shift` or `F5`, and
+    /// controller buttons as in SDL's game controller mappings, e.g. `a`,
+    /// `start` or `dpup`. Stick and trigger axes are bound by direction, e.g.
+    /// `leftx-`. Names are matched case-insensitively. A key or button only
+    /// triggers one binding, so binding it again moves it. Binding to nothing
+    /// unbinds.
+    pub fn parse(text: &str, rom: Option<&str>) -> Result<Keymap, KeymapError> {
+        let mut keymap = Keymap::default();
+        let mut overrides = Vec::new();
+        let mut section = None;
+
+        for (n, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            let error = |message: String| Err(KeymapError { line: n, message });
+
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = match header.strip_suffix(']') {
+                    Some(header) => header.trim(),
+                    None => return error("expected `]`".to_string())
+                };
+
+                section = match parse_section(header, rom) {
+                    Some(section) => Some(section),
+                    None => return error(format!("unknown section `{}`", header))
+                };
+
+                continue;
+            }
+
+            let (name, keys) = match line.split_once('=') {
+                Some((name, keys)) => (name.trim(), split_keys(keys)),
+                None => return error("expected `<binding> = <keys>`".to_string())
+            };
+
+            let binding = match Binding::from_name(name) {
+                Some(binding) => binding,
+                None => return error(format!("unknown key or hotkey `{}`", name))
+            };
+
+            match section {
+                Some(Section::Bindings(device)) => keymap.bind(device, binding, &keys),
+                Some(Section::Override(device)) => overrides.push((device, binding, keys)),
+                Some(Section::Ignored) => (),
+                None => return error("binding outside of a section".to_string())
+            }
+        }
+
+        for (device, binding, keys) in overrides {
+            keymap.bind(device, binding, &keys);
+        }
+
+        Ok(keymap)
+    }
+
+    /// Replaces the keys or buttons of `device` bound to `binding` with `keys`.
+    pub fn bind(&mut self, device: Device, binding: Binding, keys: &[String]) {
+        self.bindings.retain(|(bound_device, _), bound| *bound_device != device || *bound != binding);
+
+        for key in keys {
+            self.bindings.insert((device, key.to_ascii_lowercase()), binding);
+        }
+    }
+
+    /// Looks up the binding for a key or button name.
+    pub fn get(&self, device: Device, key: &str) -> Option<Binding> {
+        self.bindings.get(&(device, key.to_ascii_lowercase())).copied()
+    }
+
+    /// The keys or buttons of `device` bound to `binding`, sorted by name.
+    pub fn keys(&self, device: Device, binding: Binding) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.bindings.iter()
+            .filter(|((bound_device, _), bound)| *bound_device == device && **bound == binding)
+            .map(|((_, key), _)| key.as_str())
+            .collect();
+
+        keys.sort_unstable();
+        keys
+    }
+}
@@ -1 +1,160 @@
-pub mod chars;
\ No newline at end of file
+pub mod capture;
+pub mod chars;
+pub mod gif;
+pub mod keymap;
+pub mod palette;
+pub mod phosphor;
+pub mod png;
+pub mod screenshot;
+pub mod sound;
+pub mod terminal;
+
+use std::time::{Duration, Instant};
+
+use super::cpu::{CPU, CPUEnvironment};
+
+/// Emulator functions bound to keys, independent of the CHIP-8 keypad.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    SaveState,
+    LoadState,
+    NextSlot,
+    PreviousSlot,
+    /// Plays the game backwards while held.
+    Rewind,
+    /// Runs faster than real time while held.
+    FastForward,
+    /// Toggles running slower than real time.
+    SlowMotion,
+    /// Toggles pausing emulation.
+    Pause,
+    /// Restarts the program from power-on.
+    Reset,
+    /// Saves an image of the display.
+    Screenshot,
+    /// Starts or stops recording the display to a GIF.
+    Capture,
+    Fullscreen,
+    /// Pauses into the debugger.
+    Debug
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// A CHIP-8 keypad key was pressed or released.
+    Key { key: u8, pressed: bool },
+    Action { action: Action, pressed: bool },
+    Quit
+}
+
+/// Shows the CHIP-8 framebuffer to the user.
+pub trait Display {
+    /// Presents the current contents of `env.display`. Called once per frame.
+    fn present(&mut self, env: &CPUEnvironment);
+
+    /// Shows a short status line such as a crash message, or clears it.
+    fn set_status(&mut self, _status: Option<&str>) {}
+
+    /// Switches between windowed and fullscreen, where supported.
+    fn toggle_fullscreen(&mut self) {}
+
+    /// Shows the CPU's registers alongside the display, where there's room.
+    /// Called before each `present`.
+    fn show_registers(&mut self, _cpu: &CPU) {}
+
+    /// Leaves stdin and stdout to the debugger's prompt while `active`.
+    fn set_debugging(&mut self, _active: bool) {}
+}
+
+/// Source of keypad and emulator control input.
+pub trait Input {
+    /// Returns the events that happened since the last call without blocking.
+    fn poll(&mut self) -> Vec<Event>;
+}
+
+/// Produces the sound for the sound timer.
+pub trait Audio {
+    /// Called once per frame. The buzzer sounds while `sound_timer` is
+    /// nonzero, playing `env.audio_pattern` at `env.audio_frequency()`.
+    fn update(&mut self, sound_timer: u8, env: &CPUEnvironment);
+}
+
+/// Time source used to pace emulation.
+pub trait Clock {
+    /// Time elapsed since an arbitrary fixed point.
+    fn now(&self) -> Duration;
+
+    fn sleep(&mut self, duration: Duration);
+}
+
+/// Discards everything it is asked to present.
+pub struct NullDisplay;
+
+impl Display for NullDisplay {
+    fn present(&mut self, _env: &CPUEnvironment) {}
+}
+
+/// Input that never produces any events.
+pub struct NullInput;
+
+impl Input for NullInput {
+    fn poll(&mut self) -> Vec<Event> {
+        Vec::new()
+    }
+}
+
+/// Silent audio backend for headless use.
+pub struct NullAudio;
+
+impl Audio for NullAudio {
+    fn update(&mut self, _sound_timer: u8, _env: &CPUEnvironment) {}
+}
+
+/// Wall-clock time.
+pub struct SystemClock {
+    start: Instant
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// Clock that only advances when slept on, for tests and offline rendering.
+#[derive(Default)]
+pub struct ManualClock {
+    now: Duration
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        self.now
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.now += duration;
+    }
+}
@@ -0,0 +1,72 @@
+pub type Rgb = (u8, u8, u8);
+
+/// Colours for each display colour index, i.e. each combination of lit planes:
+/// unlit, plane 0 only, plane 1 only and both planes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub colours: [Rgb; 4]
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette { colours: [(0, 0, 0), (255, 255, 255), (170, 170, 170), (85, 85, 85)] }
+    }
+}
+
+/// Names accepted by `Palette::from_name`.
+pub const PALETTE_NAMES: [&str; 5] = ["default", "green", "amber", "octo", "gameboy"];
+
+fn parse_colour(text: &str) -> Option<Rgb> {
+    let hex = text.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+
+    let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((component(0)?, component(2)?, component(4)?))
+}
+
+/// Mixes `fraction` of `b` into `a`.
+pub fn blend(a: Rgb, b: Rgb, fraction: f32) -> Rgb {
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+    (mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+}
+
+impl Palette {
+    pub fn from_name(name: &str) -> Option<Palette> {
+        match name.to_ascii_lowercase().as_str() {
+            "default" => Some(Palette::default()),
+            "green" => Some(Palette { colours: [(0x0F, 0x1F, 0x0F), (0x33, 0xFF, 0x66), (0x1A, 0x99, 0x40), (0x99, 0xFF, 0xB3)] }),
+            "amber" => Some(Palette { colours: [(0x1F, 0x12, 0x00), (0xFF, 0xB0, 0x00), (0x99, 0x66, 0x00), (0xFF, 0xD8, 0x80)] }),
+            "octo" => Some(Palette { colours: [(0x99, 0x66, 0x00), (0xFF, 0xCC, 0x00), (0xFF, 0x66, 0x00), (0x66, 0x22, 0x00)] }),
+            "gameboy" => Some(Palette { colours: [(0x9B, 0xBC, 0x0F), (0x0F, 0x38, 0x0F), (0x30, 0x62, 0x30), (0x8B, 0xAC, 0x0F)] }),
+            _ => None
+        }
+    }
+
+    /// Parses a palette name or a comma-separated list of hex colours, e.g.
+    /// `#000000,#33FF66`. A list of two colours gives the background and
+    /// foreground, with shades between them for the other planes; a list of
+    /// four gives every colour.
+    pub fn parse(text: &str) -> Option<Palette> {
+        if let Some(palette) = Palette::from_name(text) {
+            return Some(palette);
+        }
+
+        let colours = text.split(',').map(parse_colour).collect::<Option<Vec<Rgb>>>()?;
+
+        match *colours.as_slice() {
+            [background, foreground] => Some(Palette {
+                colours: [background, foreground, blend(background, foreground, 2.0 / 3.0), blend(background, foreground, 1.0 / 3.0)]
+            }),
+            [a, b, c, d] => Some(Palette { colours: [a, b, c, d] }),
+            _ => None
+        }
+    }
+
+    pub fn colour(&self, index: u8) -> Rgb {
+        self.colours[index as usize % self.colours.len()]
+    }
+}
@@ -0,0 +1,142 @@
+use super::palette::{blend, Palette, Rgb};
+use super::super::cpu::CPUEnvironment;
+
+/// Fraction of a pixel's brightness kept each frame after it goes dark in
+/// `PhosphorMode::Fade`.
+pub const FADE_PERSISTENCE: f32 = 0.6;
+
+/// Brightness below which a fading pixel is shown as dark.
+const MIN_BRIGHTNESS: f32 = 1.0 / 64.0;
+
+/// Post-processing that hides the flicker of XOR-drawn sprites, as the slow
+/// phosphor of old CRTs did. It only affects what is shown, the CPU's display
+/// is left as it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhosphorMode {
+    /// Shows the display as it is.
+    Off,
+    /// Shows pixels lit in either of the last two frames.
+    Blend,
+    /// Lights pixels immediately and fades them out over a few frames.
+    Fade
+}
+
+impl PhosphorMode {
+    pub fn from_name(name: &str) -> Option<PhosphorMode> {
+        match name.to_ascii_lowercase().as_str() {
+            "off" => Some(PhosphorMode::Off),
+            "blend" => Some(PhosphorMode::Blend),
+            "fade" => Some(PhosphorMode::Fade),
+            _ => None
+        }
+    }
+}
+
+fn push_pixel(pixels: &mut Vec<u8>, (r, g, b): Rgb) {
+    pixels.extend_from_slice(&[r, g, b]);
+}
+
+/// Turns frames of the display into RGB pixels through a `PhosphorMode`.
+pub struct Phosphor {
+    mode: PhosphorMode,
+    palette: Palette,
+    /// `display_version` of the last frame, if any.
+    version: Option<u64>,
+    /// Colour of each pixel in the last frame, or the last colour it was lit
+    /// in when fading.
+    colours: Vec<u8>,
+    /// Brightness of each pixel when fading.
+    brightness: Vec<f32>,
+    /// Whether the output will change without the display changing.
+    unsettled: bool,
+    pixels: Vec<u8>
+}
+
+impl Phosphor {
+    pub fn new(mode: PhosphorMode, palette: Palette) -> Phosphor {
+        Phosphor {
+            mode,
+            palette,
+            version: None,
+            colours: Vec::new(),
+            brightness: Vec::new(),
+            unsettled: false,
+            pixels: Vec::new()
+        }
+    }
+
+    pub fn mode(&self) -> PhosphorMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: PhosphorMode) {
+        self.mode = mode;
+        self.version = None;
+    }
+
+    /// RGB pixels of the display, row by row, as of the last `update`.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Processes the next frame of `env`'s display. Returns whether `pixels`
+    /// changed.
+    pub fn update(&mut self, env: &CPUEnvironment) -> bool {
+        let display = &env.display;
+        let changed = self.version != Some(env.display_version);
+
+        if display.len() != self.colours.len() || self.version.is_none() {
+            // Start over rather than blending frames of different sizes.
+            self.colours = display.clone();
+            self.brightness = display.iter().map(|colour| if *colour != 0 { 1.0 } else { 0.0 }).collect();
+        } else if !changed && !self.unsettled {
+            return false;
+        }
+
+        self.version = Some(env.display_version);
+        self.pixels.clear();
+
+        match self.mode {
+            PhosphorMode::Off => {
+                for colour in display {
+                    push_pixel(&mut self.pixels, self.palette.colour(*colour));
+                }
+
+                self.unsettled = false;
+            },
+            PhosphorMode::Blend => {
+                for (colour, previous) in display.iter().zip(&self.colours) {
+                    push_pixel(&mut self.pixels, self.palette.colour(colour | previous));
+                }
+
+                // The next frame stops showing this one's pixels.
+                self.unsettled = self.colours != *display;
+                self.colours.copy_from_slice(display);
+            },
+            PhosphorMode::Fade => {
+                self.unsettled = false;
+
+                for (i, colour) in display.iter().enumerate() {
+                    if *colour != 0 {
+                        self.colours[i] = *colour;
+                        self.brightness[i] = 1.0;
+                    } else if self.brightness[i] > 0.0 {
+                        self.brightness[i] *= FADE_PERSISTENCE;
+
+                        if self.brightness[i] < MIN_BRIGHTNESS {
+                            self.brightness[i] = 0.0;
+                        } else {
+                            self.unsettled = true;
+                        }
+                    }
+
+                    let background = self.palette.colour(0);
+                    let lit = self.palette.colour(self.colours[i]);
+                    push_pixel(&mut self.pixels, blend(background, lit, self.brightness[i]));
+                }
+            }
+        }
+
+        true
+    }
+}
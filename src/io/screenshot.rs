@@ -0,0 +1,135 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::palette::Palette;
+use super::png;
+use super::super::cpu::{CPUEnvironment, LORES_HEIGHT, LORES_WIDTH};
+
+/// Renders the display as text, one line per row. Unlit pixels are `.`, and
+/// lit ones `#`, or the digit of their colour when more than one plane is lit.
+pub fn text(env: &CPUEnvironment) -> String {
+    let mut out = String::new();
+
+    for y in 0..env.display_height as u32 {
+        for x in 0..env.display_width as u32 {
+            out.push(match env.colour(x, y) {
+                0 => '.',
+                1 => '#',
+                c => (b'0' + c) as char
+            });
+        }
+
+        out.push('\n');
+    }
+
+    out
+}
+
+/// Renders the display in `palette`'s colours, each CHIP-8 pixel `scale`
+/// image pixels wide and high.
+pub fn rgb(env: &CPUEnvironment, palette: &Palette, scale: u32) -> Vec<u8> {
+    let scale = scale.max(1) as usize;
+    let width = env.display_width as usize;
+    let mut out = Vec::with_capacity(env.display.len() * scale * scale * 3);
+
+    for row in env.display.chunks(width) {
+        let start = out.len();
+
+        for colour in row {
+            let (r, g, b) = palette.colour(*colour);
+
+            for _ in 0..scale {
+                out.extend_from_slice(&[r, g, b]);
+            }
+        }
+
+        for _ in 1..scale {
+            out.extend_from_within(start..start + width * scale * 3);
+        }
+    }
+
+    out
+}
+
+/// Renders the display as a PNG, like `rgb`.
+pub fn png(env: &CPUEnvironment, palette: &Palette, scale: u32) -> Vec<u8> {
+    let scale = scale.max(1);
+    let width = env.display_width as u32 * scale;
+    let height = env.display_height as u32 * scale;
+
+    png::encode_rgb(width, height, &rgb(env, palette, scale))
+}
+
+/// Size of screenshots and captures at `scale` image pixels per lores pixel.
+/// Images are this size in hires too, with hires pixels half as large, or
+/// scaled to the nearest pixel when `scale` is odd.
+pub fn image_size(scale: u32) -> (u32, u32) {
+    let scale = scale.max(1);
+    (LORES_WIDTH as u32 * scale, LORES_HEIGHT as u32 * scale)
+}
+
+/// Colour index of each pixel of a `width` by `height` image of `display`,
+/// `display_width` pixels wide, scaled to the nearest pixel.
+pub fn resize(display: &[u8], display_width: usize, width: u32, height: u32) -> Vec<u8> {
+    let display_width = display_width.max(1);
+    let display_height = display.len() / display_width;
+    let mut out = Vec::with_capacity((width * height) as usize);
+
+    for y in 0..height as usize {
+        let row = &display[y * display_height / height as usize * display_width..];
+
+        for x in 0..width as usize {
+            out.push(row[x * display_width / width as usize]);
+        }
+    }
+
+    out
+}
+
+/// Renders the display as a PNG of `image_size(scale)`, like screenshots
+/// taken while running.
+pub fn image_png(env: &CPUEnvironment, palette: &Palette, scale: u32) -> Vec<u8> {
+    let (width, height) = image_size(scale);
+    let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+
+    for colour in resize(&env.display, env.display_width as usize, width, height) {
+        let (r, g, b) = palette.colour(colour);
+        rgb.extend_from_slice(&[r, g, b]);
+    }
+
+    png::encode_rgb(width, height, &rgb)
+}
+
+/// Converts days since 1970-01-01 to a year, month and day.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's algorithm, counting in 400 year eras from 0000-03-01.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Name for a screenshot taken at `time`, e.g.
+/// `screenshot-20240131-235959-042.png`, in UTC.
+pub fn file_name(time: SystemTime) -> String {
+    timestamped_file_name("screenshot", "png", time)
+}
+
+/// Name for a file created at `time`, like `file_name`.
+pub fn timestamped_file_name(prefix: &str, extension: &str, time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs() as i64;
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    let secs_of_day = secs.rem_euclid(86_400);
+
+    format!("{}-{:04}{:02}{:02}-{:02}{:02}{:02}-{:03}.{}",
+        prefix, year, month, day,
+        secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60,
+        since_epoch.subsec_millis(), extension)
+}
@@ -0,0 +1,126 @@
+use std::f32::consts::PI;
+
+use super::super::cpu::CPUEnvironment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sine,
+    Triangle
+}
+
+impl Waveform {
+    pub fn from_name(name: &str) -> Option<Waveform> {
+        match name.to_ascii_lowercase().as_str() {
+            "square" => Some(Waveform::Square),
+            "sine" => Some(Waveform::Sine),
+            "triangle" => Some(Waveform::Triangle),
+            _ => None
+        }
+    }
+
+    /// Value of the wave at `phase`, in cycles from 0 to 1.
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => if phase < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sine => (phase * 2.0 * PI).sin(),
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs()
+        }
+    }
+}
+
+/// The buzzer sound played while the sound timer is nonzero.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tone {
+    pub frequency: f32,
+    pub waveform: Waveform,
+    /// Amplitude from 0 (muted) to 1.
+    pub volume: f32
+}
+
+impl Default for Tone {
+    fn default() -> Tone {
+        Tone { frequency: 440.0, waveform: Waveform::Square, volume: 0.25 }
+    }
+}
+
+/// Number of bits in the XO-CHIP audio pattern.
+const PATTERN_BITS: f32 = 128.0;
+
+/// Generates the samples of the buzzer. Audio backends call `update` once per
+/// frame and `fill` whenever their output needs more samples.
+///
+/// Once an XO-CHIP program loads a nonzero audio pattern, the pattern is
+/// played at the program's pitch instead of the tone.
+pub struct Synth {
+    tone: Tone,
+    sample_rate: f32,
+    playing: bool,
+    /// Position within the current wave cycle, or the pattern bit being played.
+    phase: f32,
+    pattern: Option<[u8; 16]>,
+    pattern_frequency: f32
+}
+
+impl Synth {
+    pub fn new(tone: Tone, sample_rate: u32) -> Synth {
+        Synth {
+            tone,
+            sample_rate: sample_rate as f32,
+            playing: false,
+            phase: 0.0,
+            pattern: None,
+            pattern_frequency: 0.0
+        }
+    }
+
+    pub fn tone(&self) -> Tone {
+        self.tone
+    }
+
+    pub fn set_tone(&mut self, tone: Tone) {
+        self.tone = tone;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn update(&mut self, sound_timer: u8, env: &CPUEnvironment) {
+        self.playing = sound_timer > 0;
+
+        if env.audio_pattern.iter().any(|b| *b != 0) {
+            self.pattern = Some(env.audio_pattern);
+            self.pattern_frequency = env.audio_frequency();
+        } else {
+            self.pattern = None;
+        }
+    }
+
+    pub fn fill(&mut self, out: &mut [f32]) {
+        if !self.playing {
+            out.iter_mut().for_each(|sample| *sample = 0.0);
+            return;
+        }
+
+        let volume = self.tone.volume.clamp(0.0, 1.0);
+
+        for sample in out.iter_mut() {
+            *sample = match self.pattern {
+                Some(pattern) => {
+                    let bit = self.phase as usize;
+                    let set = pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+                    self.phase = (self.phase + self.pattern_frequency / self.sample_rate) % PATTERN_BITS;
+
+                    if set { volume } else { -volume }
+                },
+                None => {
+                    let phase = self.phase.fract();
+                    self.phase = (phase + self.tone.frequency / self.sample_rate).fract();
+
+                    self.tone.waveform.sample(phase) * volume
+                }
+            };
+        }
+    }
+}
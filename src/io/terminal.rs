@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+use super::palette::Rgb;
+use super::super::cpu::CPU;
+use super::super::cpu::instruction::Instruction;
+
+/// How long a key counts as held after it was first pressed. Terminals only
+/// report presses, repeated while a key is held, and most wait a few hundred
+/// milliseconds before the first repeat.
+pub const FIRST_KEY_HOLD_TIME: Duration = Duration::from_millis(500);
+
+/// How long a key counts as held after it was repeated. Repeats come much
+/// faster than the first one.
+pub const KEY_HOLD_TIME: Duration = Duration::from_millis(150);
+
+/// Columns taken up by `register_panel`.
+pub const PANEL_WIDTH: usize = 20;
+
+fn pixel(rgb: &[u8]) -> Rgb {
+    (rgb[0], rgb[1], rgb[2])
+}
+
+/// Renders RGB pixels, `width` per row, as lines of `▀` half blocks, each
+/// showing one row of pixels in the foreground colour above the next in the
+/// background colour. The colours are set with 24-bit escape codes and reset
+/// at the end of each line.
+pub fn half_blocks(pixels: &[u8], width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for rows in pixels.chunks_exact(width * 6) {
+        let (top, bottom) = rows.split_at(width * 3);
+        let mut line = String::new();
+        let mut colours: Option<(Rgb, Rgb)> = None;
+
+        for (upper, lower) in top.chunks(3).zip(bottom.chunks(3)) {
+            let (fg, bg) = (pixel(upper), pixel(lower));
+
+            if colours.map(|(last_fg, _)| last_fg) != Some(fg) {
+                let _ = write!(line, "\x1b[38;2;{};{};{}m", fg.0, fg.1, fg.2);
+            }
+
+            if colours.map(|(_, last_bg)| last_bg) != Some(bg) {
+                let _ = write!(line, "\x1b[48;2;{};{};{}m", bg.0, bg.1, bg.2);
+            }
+
+            colours = Some((fg, bg));
+            line.push('▀');
+        }
+
+        line.push_str("\x1b[0m");
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Lists the registers and the next instruction, one line per entry, each
+/// at most `PANEL_WIDTH` columns wide.
+pub fn register_panel(cpu: &CPU) -> Vec<String> {
+    let regs = &cpu.regs;
+    let mut lines = Vec::new();
+
+    for x in 0..8 {
+        lines.push(format!("V{:X} {:02X}     V{:X} {:02X}", x, regs.v(x).unwrap_or(0), x + 8, regs.v(x + 8).unwrap_or(0)));
+    }
+
+    lines.push(String::new());
+    lines.push(format!("PC {:04X}   I {:04X}", regs.pc, regs.i));
+    lines.push(format!("DT {:02X}     ST {:02X}", regs.dt, regs.st));
+    lines.push(format!("SP {}", cpu.stack().len()));
+    lines.push(String::new());
+
+    let opcode = cpu.mem.block(regs.pc as usize, 2).map(|b| ((b[0] as u16) << 8) | b[1] as u16);
+
+    lines.push(match opcode {
+        Some(opcode) => match Instruction::decode(opcode) {
+            Ok(inst) => format!("{:04X} {}", opcode, inst),
+            Err(_) => format!("{:04X} ???", opcode)
+        },
+        None => "---- out of memory".to_string()
+    });
+
+    for line in &mut lines {
+        if line.len() > PANEL_WIDTH {
+            line.truncate(PANEL_WIDTH);
+        }
+    }
+
+    lines
+}
+
+/// Name of the key sent as the escape sequence `ESC [ <number> ~`.
+fn tilde_key(number: u32) -> Option<&'static str> {
+    Some(match number {
+        1 | 7 => "Home",
+        2 => "Insert",
+        3 => "Delete",
+        4 | 8 => "End",
+        5 => "PageUp",
+        6 => "PageDown",
+        11 => "F1",
+        12 => "F2",
+        13 => "F3",
+        14 => "F4",
+        15 => "F5",
+        17 => "F6",
+        18 => "F7",
+        19 => "F8",
+        20 => "F9",
+        21 => "F10",
+        23 => "F11",
+        24 => "F12",
+        _ => return None
+    })
+}
+
+/// Name of the key sent as an escape sequence ending in `last`, other than
+/// the `~` ones.
+fn letter_key(last: u8) -> Option<&'static str> {
+    Some(match last {
+        b'A' => "Up",
+        b'B' => "Down",
+        b'C' => "Right",
+        b'D' => "Left",
+        b'H' => "Home",
+        b'F' => "End",
+        b'P' => "F1",
+        b'Q' => "F2",
+        b'R' => "F3",
+        b'S' => "F4",
+        _ => return None
+    })
+}
+
+/// Decodes the escape sequence at the start of `input`, after the `ESC`.
+/// Returns the key, if known, and the length of the sequence.
+fn escape_sequence(input: &[u8]) -> (Option<&'static str>, usize) {
+    match input {
+        // The Linux console sends F1 to F5 as `ESC [ [ A` to `ESC [ [ E`.
+        [b'[', b'[', last, ..] => {
+            let key = match *last {
+                b'A'..=b'D' => letter_key(*last - b'A' + b'P'),
+                b'E' => Some("F5"),
+                _ => None
+            };
+
+            (key, 3)
+        },
+        [b'[', rest @ ..] => {
+            // Parameters, such as modifiers in `ESC [ 1 ; 5 A`, up to the final byte.
+            let params = rest.iter().take_while(|b| (0x30..=0x3F).contains(*b)).count();
+
+            match rest.get(params) {
+                Some(b'~') => {
+                    let number = rest[..params].split(|b| *b == b';').next()
+                        .and_then(|n| std::str::from_utf8(n).ok())
+                        .and_then(|n| n.parse().ok());
+
+                    (number.and_then(tilde_key), params + 2)
+                },
+                Some(last) => (letter_key(*last), params + 2),
+                None => (None, params + 1)
+            }
+        },
+        [b'O', last, ..] => (letter_key(*last), 2),
+        _ => (Some("Escape"), 0)
+    }
+}
+
+/// Splits what a terminal in raw mode sent into the names of the keys
+/// pressed, as used in keymaps. Letters are upper case whether or not shift
+/// was held, and control characters other than Backspace, Tab and Return
+/// come out as e.g. `Ctrl+C`. Unknown escape sequences are skipped.
+pub fn key_names(input: &[u8]) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+
+    while i < input.len() {
+        let byte = input[i];
+        i += 1;
+
+        let key = match byte {
+            0x1B => {
+                let (key, len) = escape_sequence(&input[i..]);
+                i += len;
+
+                match key {
+                    Some(key) => key.to_string(),
+                    None => continue
+                }
+            },
+            0x7F | 0x08 => "Backspace".to_string(),
+            b'\t' => "Tab".to_string(),
+            b'\r' | b'\n' => "Return".to_string(),
+            b' ' => "Space".to_string(),
+            0x01..=0x1A => format!("Ctrl+{}", (b'A' + byte - 1) as char),
+            0x21..=0x7E => (byte.to_ascii_uppercase() as char).to_string(),
+            _ => continue
+        };
+
+        keys.push(key);
+    }
+
+    keys
+}
+
+/// Keys currently held, going by when they were last reported.
+pub struct HeldKeys {
+    first_hold_time: Duration,
+    hold_time: Duration,
+    /// When each held key counts as released.
+    release_times: HashMap<String, Duration>
+}
+
+impl HeldKeys {
+    /// Creates a set of keys that count as held for `first_hold_time` after
+    /// they're pressed, and for `hold_time` after each repeat.
+    pub fn new(first_hold_time: Duration, hold_time: Duration) -> HeldKeys {
+        HeldKeys { first_hold_time, hold_time, release_times: HashMap::new() }
+    }
+
+    /// Notes that `key` was reported at `now`. Returns whether it was newly
+    /// pressed, rather than still held.
+    pub fn press(&mut self, key: &str, now: Duration) -> bool {
+        match self.release_times.get_mut(key) {
+            Some(release_time) => {
+                *release_time = now + self.hold_time;
+                false
+            },
+            None => {
+                self.release_times.insert(key.to_string(), now + self.first_hold_time);
+                true
+            }
+        }
+    }
+
+    /// Releases the keys that haven't been reported again by `now`,
+    /// returning their names in order.
+    pub fn release(&mut self, now: Duration) -> Vec<String> {
+        let mut released: Vec<String> = self.release_times.iter()
+            .filter(|(_, release_time)| **release_time <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        released.sort();
+
+        for key in &released {
+            self.release_times.remove(key);
+        }
+
+        released
+    }
+}
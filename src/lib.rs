@@ -1,4 +1,11 @@
 extern crate rand;
 
 pub mod cpu;
-pub mod io;
\ No newline at end of file
+pub mod io;
+pub mod disasm;
+pub mod asm;
+pub mod debug;
+pub mod rewind;
+pub mod movie;
+pub mod headless;
+pub mod scheduler;
\ No newline at end of file
@@ -2,21 +2,196 @@
 
 extern crate chip8;
 extern crate sdl2;
-extern crate phf;
 
+use std::env;
+use std::fs;
 use std::fs::File;
-use std::io::Read;
+use std::io::BufWriter;
+use std::path::Path;
+use std::process;
 
+use chip8::cpu::CPU;
+use chip8::cpu::memory::{Memory, XOCHIP_MEMORY_SIZE};
+use chip8::headless::{self, Limit};
+use chip8::io::{Audio, Display, Input, NullAudio, SystemClock};
+use chip8::io::capture::{Capture, CaptureFormat};
+use chip8::io::keymap::Keymap;
+use chip8::io::phosphor::Phosphor;
+use chip8::io::screenshot;
+use chip8::movie::Movie;
+
+use cli::{Command, Options};
+
+mod cli;
 mod emu;
+mod sdl;
+#[cfg(unix)]
+mod tui;
 
-fn main() {
-    let program = {
-        let mut file = File::open("game.ch8").expect("Failed to open ROM!");
-        let mut buf = Vec::new();
-        file.read_to_end(&mut buf).expect("Failed to read ROM to end");
-        buf
+type Frontend = (Box<dyn Display>, Box<dyn Input>, Box<dyn Audio>);
+
+fn read_movie(path: &str) -> Result<Movie, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("failed to read {}: {}", path, err))?;
+    Movie::parse(&text).map_err(|err| format!("{}:{}", path, err))
+}
+
+/// Loads the keymap with the overrides for the ROM being run.
+fn load_keymap(options: &Options) -> Result<Keymap, String> {
+    let path = match &options.keymap {
+        Some(path) => path.as_str(),
+        None if Path::new(cli::DEFAULT_KEYMAP).exists() => cli::DEFAULT_KEYMAP,
+        None => return Ok(Keymap::default())
+    };
+
+    let text = fs::read_to_string(path).map_err(|err| format!("failed to read {}: {}", path, err))?;
+    let rom = Path::new(&options.rom).file_name().and_then(|name| name.to_str());
+
+    Keymap::parse(&text, rom).map_err(|err| format!("{}:{}", path, err))
+}
+
+/// Creates the CPU with `program` loaded according to the options.
+fn create_cpu(options: &Options, program: &[u8]) -> Result<CPU, String> {
+    let mut cpu = match options.seed {
+        Some(seed) => CPU::with_seed(options.quirks, seed),
+        None => CPU::with_quirks(options.quirks)
     };
 
-    let mut emulator = emu::Emulator::new();
+    if options.xochip {
+        cpu.mem = Memory::with_size(XOCHIP_MEMORY_SIZE);
+    }
+
+    if cpu.mem.load(options.load_address as usize, program).is_none() {
+        return Err(format!("{} ({} bytes) doesn't fit in memory when loaded at {:#05X}",
+            options.rom, program.len(), options.load_address));
+    }
+
+    cpu.regs.pc = options.load_address;
+
+    Ok(cpu)
+}
+
+/// Runs the ROM without a window and prints the final display and registers.
+fn run_headless(mut cpu: CPU, options: &Options, movie: Option<Movie>) -> Result<(), String> {
+    let mut headless_options = headless::Options {
+        instructions_per_frame: options.instructions_per_frame,
+        ..headless::Options::default()
+    };
+
+    if let Some(movie) = movie {
+        cpu.reseed(movie.seed);
+        headless_options.limit = Limit::Frames(movie.length);
+        headless_options.instructions_per_frame = movie.instructions_per_frame;
+        headless_options.input = movie.events;
+    }
+
+    if let Some(frames) = options.frames {
+        headless_options.limit = Limit::Frames(frames);
+    }
+
+    let report = match &options.capture {
+        Some(path) => {
+            let file = File::create(path).map_err(|err| format!("failed to create {}: {}", path, err))?;
+            let format = CaptureFormat::from_path(path);
+            let mut capture = Capture::new(BufWriter::new(file), format, options.palette, options.scale)
+                .map_err(|err| format!("failed to write {}: {}", path, err))?;
+
+            let report = headless::run_with(&mut cpu, &headless_options, &mut capture, &mut NullAudio);
+            capture.finish().map_err(|err| format!("failed to write {}: {}", path, err))?;
+            report
+        },
+        None => headless::run(&mut cpu, &headless_options)
+    };
+
+    print!("{}", screenshot::text(&cpu.env));
+    print!("{}", headless::registers_json(&cpu, &report));
+
+    match report.error {
+        Some(err) => Err(format!("CPU crashed: {}", err)),
+        None => Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn init_tui(phosphor: Phosphor, keymap: Keymap) -> Result<Frontend, String> {
+    let (display, input, audio) = tui::init(phosphor, keymap)?;
+    Ok((Box::new(display), Box::new(input), Box::new(audio)))
+}
+
+#[cfg(not(unix))]
+fn init_tui(_phosphor: Phosphor, _keymap: Keymap) -> Result<Frontend, String> {
+    Err("--tui is only supported on Unix".to_string())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let program = fs::read(&options.rom).map_err(|err| format!("failed to read {}: {}", options.rom, err))?;
+
+    let movie = match &options.play {
+        Some(path) => Some(read_movie(path)?),
+        None => None
+    };
+
+    let cpu = create_cpu(&options, &program)?;
+
+    if options.headless {
+        return run_headless(cpu, &options, movie);
+    }
+
+    let keymap = load_keymap(&options)?;
+    let phosphor = Phosphor::new(options.filter, options.palette);
+
+    let (display, input, audio) = if options.tui {
+        init_tui(phosphor, keymap)?
+    } else {
+        let (mut display, input, audio) = sdl::init(options.tone, options.scale, phosphor, keymap)?;
+
+        if options.fullscreen {
+            display.toggle_fullscreen();
+        }
+
+        (Box::new(display) as Box<dyn Display>, Box::new(input) as Box<dyn Input>, audio)
+    };
+
+    let mut emulator = emu::Emulator::new(display, input, audio, Box::new(SystemClock::new()), cpu);
+
+    emulator.set_instructions_per_frame(options.instructions_per_frame);
+    emulator.set_paused(options.paused);
+    emulator.set_break_on_start(options.debug);
+    emulator.set_image_style(options.palette, options.scale);
+
+    if let Some(path) = &options.capture {
+        emulator.capture(path).map_err(|err| format!("failed to start capture to {}: {}", path, err))?;
+    }
+
+    if let Some(path) = &options.record {
+        emulator.record(path);
+    }
+
+    if let Some(movie) = movie {
+        emulator.play(movie);
+    }
+
     emulator.start(&program);
-}
\ No newline at end of file
+
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let options = match cli::parse(args.iter().map(String::as_str)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        },
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    if let Err(message) = run(options) {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+}
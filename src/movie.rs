@@ -0,0 +1,214 @@
+use std::error::Error;
+use std::fmt;
+
+use super::cpu::CPU;
+
+pub const MOVIE_MAGIC: &str = "CHIP8MOVIE";
+pub const MOVIE_VERSION: u32 = 2;
+
+/// The keypad state after a change, as a bitmask with bit `n` set if key `n`
+/// is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub frame: u32,
+    pub keys: u16
+}
+
+/// A recording of every keypad change made while running a ROM from power-on.
+/// Together with the RNG seed this reproduces a run exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub seed: u64,
+    pub rom_hash: u64,
+    /// Number of frames the recording ran for.
+    pub length: u32,
+    /// Instructions executed per frame, which has to match for the input
+    /// to line up.
+    pub instructions_per_frame: u32,
+    /// Changes in the order they happened. A frame can have several.
+    pub events: Vec<InputEvent>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MovieError {
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.line, self.message)
+    }
+}
+
+impl Error for MovieError {}
+
+/// 64-bit FNV-1a hash, used to check a movie is played back on the ROM it
+/// was recorded with.
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    rom.iter().fold(0xCBF2_9CE4_8422_2325, |hash, b| (hash ^ *b as u64).wrapping_mul(0x0100_0000_01B3))
+}
+
+pub fn keys_mask(keyboard: &[bool; 16]) -> u16 {
+    keyboard.iter().enumerate().fold(0, |keys, (i, pressed)| if *pressed { keys | 1 << i } else { keys })
+}
+
+/// Sets the keypad to `keys`, pressing every newly held key so that a
+/// pending `LD Vx, K` sees it.
+pub fn set_keys(cpu: &mut CPU, keys: u16) {
+    for key in 0..16 {
+        let pressed = keys & (1 << key) != 0;
+        let was_pressed = cpu.env.keyboard[key as usize];
+        cpu.env.keyboard[key as usize] = pressed;
+
+        if pressed && !was_pressed {
+            cpu.press_key(key);
+        }
+    }
+}
+
+fn parse_error<T>(line: usize, message: &str) -> Result<T, MovieError> {
+    Err(MovieError { line, message: message.to_string() })
+}
+
+fn parse_field<'a>(line: usize, text: &'a str, name: &str) -> Result<&'a str, MovieError> {
+    let mut words = text.split_whitespace();
+
+    match (words.next(), words.next(), words.next()) {
+        (Some(key), Some(value), None) if key == name => Ok(value),
+        _ => parse_error(line, &format!("expected `{} <value>`", name))
+    }
+}
+
+fn parse_hex<T>(line: usize, text: &str, parse: fn(&str, u32) -> Result<T, std::num::ParseIntError>)
+    -> Result<T, MovieError> {
+    parse(text, 16).or_else(|_| parse_error(line, &format!("invalid number `{}`", text)))
+}
+
+impl Movie {
+    /// Parses the text format written by `Display`.
+    pub fn parse(text: &str) -> Result<Movie, MovieError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let (n, header) = lines.next().unwrap_or((1, ""));
+        if header.strip_prefix(MOVIE_MAGIC).map(str::trim) != Some(MOVIE_VERSION.to_string().as_str()) {
+            return parse_error(n, &format!("not a version {} movie", MOVIE_VERSION));
+        }
+
+        let mut field = |name| match lines.next() {
+            Some((n, line)) => parse_field(n, line, name).map(|value| (n, value)),
+            None => parse_error(n, &format!("missing `{}`", name))
+        };
+
+        let (n, seed) = field("seed")?;
+        let seed = parse_hex(n, seed, u64::from_str_radix)?;
+        let (n, hash) = field("rom")?;
+        let rom_hash = parse_hex(n, hash, u64::from_str_radix)?;
+        let (n, length) = field("length")?;
+        let length = length.parse().or_else(|_| parse_error(n, "invalid length"))?;
+
+        let (n, ipf) = field("ipf")?;
+        let instructions_per_frame = ipf.parse().or_else(|_| parse_error(n, "invalid instructions per frame"))?;
+
+        let mut events: Vec<InputEvent> = Vec::new();
+
+        for (n, line) in lines {
+            let mut words = line.split_whitespace();
+
+            let event = match (words.next(), words.next(), words.next()) {
+                (Some(frame), Some(keys), None) => InputEvent {
+                    frame: frame.parse().or_else(|_| parse_error(n, "invalid frame number"))?,
+                    keys: parse_hex(n, keys, u16::from_str_radix)?
+                },
+                _ => return parse_error(n, "expected `<frame> <keys>`")
+            };
+
+            if matches!(events.last(), Some(last) if last.frame > event.frame) {
+                return parse_error(n, "events are out of order");
+            }
+
+            events.push(event);
+        }
+
+        Ok(Movie { seed, rom_hash, length, instructions_per_frame, events })
+    }
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} {}", MOVIE_MAGIC, MOVIE_VERSION)?;
+        writeln!(f, "seed {:016x}", self.seed)?;
+        writeln!(f, "rom {:016x}", self.rom_hash)?;
+        writeln!(f, "length {}", self.length)?;
+        writeln!(f, "ipf {}", self.instructions_per_frame)?;
+
+        for event in &self.events {
+            writeln!(f, "{} {:04x}", event.frame, event.keys)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Recorder {
+    movie: Movie,
+    keys: u16
+}
+
+impl Recorder {
+    pub fn new(seed: u64, rom: &[u8], instructions_per_frame: u32) -> Recorder {
+        Recorder {
+            movie: Movie { seed, rom_hash: rom_hash(rom), length: 0, instructions_per_frame, events: Vec::new() },
+            keys: 0
+        }
+    }
+
+    /// Logs the keypad state if it changed since the last call.
+    pub fn record(&mut self, frame: u32, keyboard: &[bool; 16]) {
+        let keys = keys_mask(keyboard);
+
+        if keys != self.keys {
+            self.movie.events.push(InputEvent { frame, keys });
+            self.keys = keys;
+        }
+    }
+
+    /// Ends the recording after `length` frames.
+    pub fn finish(mut self, length: u32) -> Movie {
+        self.movie.length = length;
+        self.movie
+    }
+}
+
+pub struct Player {
+    movie: Movie,
+    next: usize
+}
+
+impl Player {
+    pub fn new(movie: Movie) -> Player {
+        Player { movie, next: 0 }
+    }
+
+    pub fn movie(&self) -> &Movie {
+        &self.movie
+    }
+
+    /// Applies the input changes recorded for `frame`. Must be called once
+    /// per frame, in order, before the CPU runs that frame.
+    pub fn apply(&mut self, frame: u32, cpu: &mut CPU) {
+        while let Some(event) = self.movie.events.get(self.next) {
+            if event.frame > frame {
+                break;
+            }
+
+            set_keys(cpu, event.keys);
+            self.next += 1;
+        }
+    }
+
+    pub fn is_finished(&self, frame: u32) -> bool {
+        frame >= self.movie.length
+    }
+}
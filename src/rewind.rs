@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+/// Ring buffer of save states for playing a game backwards.
+///
+/// Only the newest state is kept in full. Every older state is stored as the
+/// XOR of itself with the state after it, run-length encoded. Consecutive
+/// frames differ in only a few bytes, so each delta is tiny.
+pub struct Rewind {
+    capacity: usize,
+    latest: Option<Vec<u8>>,
+    /// Deltas ordered oldest first. Applying the last one to `latest` yields
+    /// the state before it.
+    deltas: VecDeque<Vec<u8>>
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: usize) {
+    while n >= 0x80 {
+        out.push((n as u8 & 0x7F) | 0x80);
+        n >>= 7;
+    }
+
+    out.push(n as u8);
+}
+
+/// Reads a varint, or returns `None` if it's cut off or doesn't fit.
+fn read_varint(data: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut n = 0usize;
+    let mut shift = 0;
+
+    loop {
+        let b = *data.get(*pos)?;
+        *pos += 1;
+
+        let bits = ((b & 0x7F) as usize).checked_shl(shift)?;
+        if bits >> shift != (b & 0x7F) as usize {
+            return None;
+        }
+
+        n |= bits;
+        shift += 7;
+
+        if b & 0x80 == 0 {
+            return Some(n);
+        }
+    }
+}
+
+/// Encodes the difference between `from` and `to` so that `apply` can turn
+/// `from` into `to`.
+pub fn diff(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let len = from.len().max(to.len());
+    let xor = |i: usize| from.get(i).unwrap_or(&0) ^ to.get(i).unwrap_or(&0);
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    write_varint(&mut out, to.len());
+
+    while i < len {
+        let start = i;
+        while i < len && xor(i) == 0 {
+            i += 1;
+        }
+
+        let zeros = i - start;
+        let start = i;
+        while i < len && xor(i) != 0 {
+            i += 1;
+        }
+
+        write_varint(&mut out, zeros);
+        write_varint(&mut out, i - start);
+        out.extend((start..i).map(xor));
+    }
+
+    out
+}
+
+/// Turns `state` into the state a delta from `diff` was made for. Returns
+/// `None` if the delta is malformed, e.g. if it changes bytes past the end of
+/// the state.
+pub fn apply(state: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    let mut pos = 0;
+    let len = read_varint(delta, &mut pos)?;
+    let mut out = state.to_vec();
+    let mut i = 0usize;
+
+    out.resize(out.len().max(len), 0);
+
+    while pos < delta.len() {
+        let start = i.checked_add(read_varint(delta, &mut pos)?)?;
+        let literal = read_varint(delta, &mut pos)?;
+        let end = start.checked_add(literal)?;
+        let bytes = delta.get(pos..pos.checked_add(literal)?)?;
+
+        for (b, x) in out.get_mut(start..end)?.iter_mut().zip(bytes) {
+            *b ^= x;
+        }
+
+        i = end;
+        pos += literal;
+    }
+
+    out.truncate(len);
+    Some(out)
+}
+
+impl Rewind {
+    /// Creates a buffer that remembers up to `capacity` states.
+    pub fn new(capacity: usize) -> Rewind {
+        Rewind { capacity, latest: None, deltas: VecDeque::with_capacity(capacity) }
+    }
+
+    /// Number of states that can currently be rewound to, including the newest.
+    pub fn len(&self) -> usize {
+        match self.latest {
+            Some(_) => self.deltas.len() + 1,
+            None => 0
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.latest = None;
+        self.deltas.clear();
+    }
+
+    /// Approximate number of bytes used to store the states.
+    pub fn memory_usage(&self) -> usize {
+        self.latest.as_ref().map_or(0, Vec::len) + self.deltas.iter().map(Vec::len).sum::<usize>()
+    }
+
+    /// Records a new state, dropping the oldest one if the buffer is full.
+    pub fn push(&mut self, state: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Some(latest) = self.latest.take() {
+            self.deltas.push_back(diff(&state, &latest));
+
+            while self.deltas.len() + 1 > self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+
+        self.latest = Some(state);
+    }
+
+    /// Removes the newest state and returns the one recorded before it,
+    /// which becomes the newest. Returns `None` once the oldest state has
+    /// been reached, or if the older state can't be decoded.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let delta = self.deltas.pop_back()?;
+        let latest = self.latest.as_ref()?;
+        let previous = apply(latest, &delta)?;
+
+        self.latest = Some(previous.clone());
+        Some(previous)
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+/// Rate of the delay and sound timers, and of emulated frames.
+pub const FRAME_RATE: u32 = 60;
+
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: u32 = 10;
+pub const MIN_INSTRUCTIONS_PER_FRAME: u32 = 1;
+pub const MAX_INSTRUCTIONS_PER_FRAME: u32 = 10_000;
+
+/// Most frames run to catch up at once. If the host falls further behind,
+/// e.g. while paused in the debugger, emulation slows down instead.
+pub const MAX_CATCH_UP_FRAMES: u32 = 4;
+
+pub const MIN_SPEED: f64 = 0.125;
+pub const MAX_SPEED: f64 = 16.0;
+
+/// Decides when emulated frames are due, so that at normal speed exactly
+/// `FRAME_RATE` frames (and timer ticks) happen per second, whatever the
+/// display's refresh rate.
+pub struct Scheduler {
+    speed: f64,
+    /// Time the current run of frames started at.
+    base: Duration,
+    /// Frames scheduled since `base`.
+    frames: u64
+}
+
+impl Scheduler {
+    pub fn new(now: Duration) -> Scheduler {
+        Scheduler { speed: 1.0, base: now, frames: 0 }
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Sets the emulation speed relative to real time, e.g. 2 for double speed.
+    pub fn set_speed(&mut self, speed: f64) {
+        let next = self.next_frame();
+
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+        self.base = next;
+        self.frames = 0;
+    }
+
+    /// Forgets any frames that are overdue, making the next one due at `now`.
+    pub fn reset(&mut self, now: Duration) {
+        self.base = now;
+        self.frames = 0;
+    }
+
+    fn next_frame(&self) -> Duration {
+        self.base + Duration::from_secs_f64(self.frames as f64 / (FRAME_RATE as f64 * self.speed))
+    }
+
+    /// Returns how many frames should be run at `now` and marks them as run.
+    pub fn frames_due(&mut self, now: Duration) -> u32 {
+        let mut due = 0;
+
+        while self.next_frame() <= now {
+            if due == MAX_CATCH_UP_FRAMES {
+                // Drop the backlog, the next frame is due one frame from now.
+                self.base = now;
+                self.frames = 1;
+                break;
+            }
+
+            self.frames += 1;
+            due += 1;
+        }
+
+        due
+    }
+
+    pub fn time_until_next_frame(&self, now: Duration) -> Duration {
+        self.next_frame().checked_sub(now).unwrap_or_default()
+    }
+}
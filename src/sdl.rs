@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+
+use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::controller::{Axis, GameController};
+use sdl2::GameControllerSubsystem;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{Texture, TextureCreator};
+use sdl2::video::{FullscreenType, WindowContext};
+
+use chip8::cpu::{CPUEnvironment, LORES_HEIGHT, LORES_WIDTH};
+use chip8::io::{Audio, Display, Event, Input, NullAudio};
+use chip8::io::keymap::{Binding, Device, Keymap};
+use chip8::io::phosphor::Phosphor;
+use chip8::io::sound::{Synth, Tone};
+
+const WINDOW_TITLE: &str = "CHIP-8 Emulator";
+
+const SAMPLE_RATE: i32 = 44100;
+
+/// How far a stick or trigger has to be pushed to count as pressed.
+const AXIS_THRESHOLD: i16 = 16384;
+
+pub struct SdlDisplay {
+    canvas: sdl2::render::WindowCanvas,
+    texture_creator: TextureCreator<WindowContext>,
+    /// Streaming texture holding the display, recreated when its size changes.
+    texture: Option<Texture>,
+    phosphor: Phosphor
+}
+
+pub struct SdlInput {
+    event_pump: sdl2::EventPump,
+    keymap: Keymap,
+    /// Missing if SDL couldn't initialise game controller support.
+    controller_subsystem: Option<GameControllerSubsystem>,
+    /// Connected controllers, which stop sending events once dropped.
+    controllers: Vec<GameController>,
+    /// Direction each controller axis is pushed in, by joystick id.
+    axes: HashMap<(u32, Axis), i8>
+}
+
+struct SynthCallback(Synth);
+
+impl AudioCallback for SynthCallback {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        self.0.fill(out);
+    }
+}
+
+/// Plays the buzzer through SDL's audio callback.
+pub struct SdlAudio {
+    device: AudioDevice<SynthCallback>
+}
+
+impl Audio for SdlAudio {
+    fn update(&mut self, sound_timer: u8, env: &CPUEnvironment) {
+        self.device.lock().0.update(sound_timer, env);
+    }
+}
+
+fn open_audio(sdl: &sdl2::Sdl, tone: Tone) -> Result<SdlAudio, String> {
+    let audio = sdl.audio()?;
+    let spec = AudioSpecDesired { freq: Some(SAMPLE_RATE), channels: Some(1), samples: None };
+
+    let device = audio.open_playback(None, &spec, |spec| SynthCallback(Synth::new(tone, spec.freq as u32)))?;
+    device.resume();
+
+    Ok(SdlAudio { device })
+}
+
+/// Opens the emulator window, `scale` window pixels per lores pixel and
+/// drawn through `phosphor`, its event queue with keys bound by `keymap` and
+/// the audio output. Without an audio device the emulator runs muted.
+pub fn init(tone: Tone, scale: u32, phosphor: Phosphor, keymap: Keymap)
+    -> Result<(SdlDisplay, SdlInput, Box<dyn Audio>), String> {
+    let sdl = sdl2::init().map_err(|err| format!("failed to initialise SDL2: {}", err))?;
+    let video = sdl.video().map_err(|err| format!("failed to initialise SDL2 video subsystem: {}", err))?;
+
+    let window = video.window(WINDOW_TITLE, LORES_WIDTH as u32 * scale, LORES_HEIGHT as u32 * scale)
+        .position_centered()
+        .resizable()
+        .build()
+        .map_err(|err| format!("failed to create window: {}", err))?;
+
+    // Frames are paced by the emulator's scheduler rather than vsync, which
+    // would tie the emulation speed to the monitor's refresh rate.
+    let canvas: sdl2::render::WindowCanvas = window.into_canvas()
+        .build()
+        .map_err(|err| format!("failed to create canvas for window: {}", err))?;
+
+    let event_pump = sdl.event_pump().map_err(|err| format!("failed to initialise SDL2 event subsystem: {}", err))?;
+
+    // Connected controllers are opened as SDL reports them being added.
+    let controller_subsystem = match sdl.game_controller() {
+        Ok(subsystem) => Some(subsystem),
+        Err(err) => {
+            eprintln!("Failed to initialise game controller support, only the keyboard can be used: {}", err);
+            None
+        }
+    };
+
+    let input = SdlInput { event_pump, keymap, controller_subsystem, controllers: Vec::new(), axes: HashMap::new() };
+
+    let audio: Box<dyn Audio> = match open_audio(&sdl, tone) {
+        Ok(audio) => Box::new(audio),
+        Err(err) => {
+            eprintln!("Failed to open audio device, sound is disabled: {}", err);
+            Box::new(NullAudio)
+        }
+    };
+
+    let texture_creator = canvas.texture_creator();
+    let display = SdlDisplay {
+        canvas,
+        texture_creator,
+        texture: None,
+        phosphor
+    };
+
+    Ok((display, input, audio))
+}
+
+/// Fits a display of `display` pixels into the window at the largest whole
+/// number of window pixels per display pixel, centred with black bars
+/// around it. Returns that scale and the top left corner.
+fn letterbox(window: (u32, u32), display: (u32, u32)) -> (u32, i32, i32) {
+    let scale = (window.0 / display.0).min(window.1 / display.1).max(1);
+    let left = (window.0 as i32 - (display.0 * scale) as i32) / 2;
+    let top = (window.1 as i32 - (display.1 * scale) as i32) / 2;
+
+    (scale, left, top)
+}
+
+impl SdlDisplay {
+    /// Uploads the next frame of the display to the texture if it changed.
+    fn update_texture(&mut self, env: &CPUEnvironment) -> Result<(), String> {
+        let w = env.display_width as u32;
+        let h = env.display_height as u32;
+
+        let resized = match &self.texture {
+            Some(texture) => {
+                let query = texture.query();
+                query.width != w || query.height != h
+            },
+            None => true
+        };
+
+        if resized {
+            if let Some(texture) = self.texture.take() {
+                // Safe as the canvas that owns the texture is still alive.
+                unsafe { texture.destroy() };
+            }
+
+            self.texture = Some(self.texture_creator.create_texture_streaming(PixelFormatEnum::RGB24, w, h)
+                .map_err(|err| err.to_string())?);
+        }
+
+        if self.phosphor.update(env) || resized {
+            let texture = self.texture.as_mut().expect("Texture should have been created");
+            texture.update(None, self.phosphor.pixels(), w as usize * 3).map_err(|err| err.to_string())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for SdlDisplay {
+    fn drop(&mut self) {
+        if let Some(texture) = self.texture.take() {
+            // Safe as the canvas is dropped after this.
+            unsafe { texture.destroy() };
+        }
+    }
+}
+
+impl Display for SdlDisplay {
+    fn present(&mut self, env: &CPUEnvironment) {
+        self.update_texture(env).expect("Failed to update display texture");
+
+        self.canvas.set_draw_color(sdl2::pixels::Color::RGB(0, 0, 0));
+        self.canvas.clear();
+
+        let w = env.display_width as u32;
+        let h = env.display_height as u32;
+        let window_size = self.canvas.output_size().expect("Failed to get window size");
+        let (scale, left, top) = letterbox(window_size, (w, h));
+
+        if let Some(texture) = &self.texture {
+            self.canvas.copy(texture, None, sdl2::rect::Rect::new(left, top, w * scale, h * scale))
+                .expect("Failed to draw display texture");
+        }
+
+        self.canvas.present();
+    }
+
+    fn set_status(&mut self, status: Option<&str>) {
+        let title = match status {
+            Some(status) => format!("{} - {}", WINDOW_TITLE, status),
+            None => WINDOW_TITLE.to_string()
+        };
+
+        self.canvas.window_mut().set_title(&title).expect("Failed to set window title");
+    }
+
+    fn toggle_fullscreen(&mut self) {
+        let window = self.canvas.window_mut();
+        let state = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off
+        };
+
+        if let Err(err) = window.set_fullscreen(state) {
+            eprintln!("Failed to toggle fullscreen: {}", err);
+        }
+    }
+}
+
+fn binding_event(binding: Binding, repeat: bool, pressed: bool) -> Option<Event> {
+    match binding {
+        Binding::Key(key) => Some(Event::Key { key, pressed }),
+        // Holding a hotkey shouldn't repeat its action.
+        Binding::Action(_) if repeat => None,
+        Binding::Action(action) => Some(Event::Action { action, pressed })
+    }
+}
+
+fn axis_name(axis: Axis, direction: i8) -> String {
+    format!("{}{}", axis.string(), if direction < 0 { '-' } else { '+' })
+}
+
+impl SdlInput {
+    fn key_event(&self, keycode: Option<Keycode>, repeat: bool, pressed: bool) -> Option<Event> {
+        binding_event(self.keymap.get(Device::Keyboard, &keycode?.name())?, repeat, pressed)
+    }
+
+    fn button_event(&self, button: &str, pressed: bool) -> Option<Event> {
+        binding_event(self.keymap.get(Device::Gamepad, button)?, false, pressed)
+    }
+
+    fn open_controller(&mut self, index: u32) {
+        if let Some(subsystem) = &self.controller_subsystem {
+            match subsystem.open(index) {
+                Ok(controller) => {
+                    println!("connected controller {}", controller.name());
+                    self.controllers.push(controller);
+                },
+                Err(err) => eprintln!("Failed to open game controller {}: {}", index, err)
+            }
+        }
+    }
+
+    fn close_controller(&mut self, id: u32) {
+        self.controllers.retain(|controller| controller.instance_id() as u32 != id);
+        self.axes.retain(|(axis_id, _), _| *axis_id != id);
+    }
+
+    /// Turns a stick or trigger into a pair of buttons, one per direction.
+    fn axis_events(&mut self, id: u32, axis: Axis, value: i16, events: &mut Vec<Event>) {
+        let direction = if value <= -AXIS_THRESHOLD {
+            -1
+        } else if value >= AXIS_THRESHOLD {
+            1
+        } else {
+            0
+        };
+
+        let previous = self.axes.insert((id, axis), direction).unwrap_or(0);
+
+        if direction == previous {
+            return;
+        }
+
+        if previous != 0 {
+            events.extend(self.button_event(&axis_name(axis, previous), false));
+        }
+
+        if direction != 0 {
+            events.extend(self.button_event(&axis_name(axis, direction), true));
+        }
+    }
+}
+
+impl Input for SdlInput {
+    fn poll(&mut self) -> Vec<Event> {
+        let mut events = Vec::new();
+        let sdl_events: Vec<sdl2::event::Event> = self.event_pump.poll_iter().collect();
+
+        for event in sdl_events {
+            let event = match event {
+                sdl2::event::Event::Quit { .. } => Some(Event::Quit),
+                sdl2::event::Event::KeyDown { keycode, repeat, .. } => self.key_event(keycode, repeat, true),
+                sdl2::event::Event::KeyUp { keycode, repeat, .. } => self.key_event(keycode, repeat, false),
+                sdl2::event::Event::ControllerButtonDown { button, .. } => self.button_event(&button.string(), true),
+                sdl2::event::Event::ControllerButtonUp { button, .. } => self.button_event(&button.string(), false),
+                sdl2::event::Event::ControllerAxisMotion { which, axis, value, .. } => {
+                    self.axis_events(which, axis, value, &mut events);
+                    None
+                },
+                sdl2::event::Event::ControllerDeviceAdded { which, .. } => {
+                    self.open_controller(which);
+                    None
+                },
+                sdl2::event::Event::ControllerDeviceRemoved { which, .. } => {
+                    self.close_controller(which);
+                    None
+                },
+                _ => None
+            };
+
+            events.extend(event);
+        }
+
+        events
+    }
+}
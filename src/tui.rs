@@ -0,0 +1,301 @@
+use std::io;
+use std::io::{Read, Write};
+use std::mem::MaybeUninit;
+use std::time::Instant;
+
+use chip8::cpu::{CPU, CPUEnvironment};
+use chip8::io::{Audio, Display, Event, Input};
+use chip8::io::keymap::{Binding, Device, Keymap};
+use chip8::io::phosphor::Phosphor;
+use chip8::io::terminal::{self, HeldKeys, FIRST_KEY_HOLD_TIME, KEY_HOLD_TIME, PANEL_WIDTH};
+
+/// Columns between the display and the register panel.
+const PANEL_GAP: usize = 2;
+
+/// Puts stdin into raw mode, so keys arrive as they are pressed, without
+/// echo or signals, and reads don't block. The original mode is restored on
+/// drop.
+struct RawMode {
+    original: libc::termios
+}
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        let mut termios = MaybeUninit::<libc::termios>::uninit();
+
+        let original = unsafe {
+            if libc::tcgetattr(libc::STDIN_FILENO, termios.as_mut_ptr()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            termios.assume_init()
+        };
+
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+        raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+        raw.c_cc[libc::VMIN] = 0;
+        raw.c_cc[libc::VTIME] = 0;
+
+        set_mode(&raw)?;
+
+        Ok(RawMode { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = set_mode(&self.original);
+    }
+}
+
+fn set_mode(termios: &libc::termios) -> io::Result<()> {
+    if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, termios) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Columns and rows of the terminal.
+fn terminal_size() -> (usize, usize) {
+    let mut size = MaybeUninit::<libc::winsize>::zeroed();
+
+    unsafe {
+        if libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, size.as_mut_ptr()) != 0 {
+            return (80, 24);
+        }
+
+        let size = size.assume_init();
+        (size.ws_col as usize, size.ws_row as usize)
+    }
+}
+
+/// Draws the display in half blocks on the alternate screen, with the
+/// registers on the right. Anything else printed scrolls by underneath.
+pub struct TuiDisplay {
+    raw_mode: Option<RawMode>,
+    phosphor: Phosphor,
+    /// Terminal and display size the screen was laid out for.
+    layout: Option<((usize, usize), (usize, usize))>,
+    /// Whether the display fits in the terminal.
+    fits: bool,
+    /// Lines of the display and the panel as last drawn, so only the ones
+    /// that changed are drawn again.
+    display_lines: Vec<String>,
+    panel_lines: Vec<String>,
+    registers: Vec<String>,
+    status: Option<String>
+}
+
+impl TuiDisplay {
+    fn enter(&mut self) -> io::Result<()> {
+        self.raw_mode = Some(RawMode::enable()?);
+        self.layout = None;
+
+        // Alternate screen, hidden cursor.
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()
+    }
+
+    fn leave(&mut self) {
+        // Whole screen scrolling, default colours, visible cursor, main screen.
+        print!("\x1b[r\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+
+        self.raw_mode = None;
+    }
+
+    /// Clears the screen for a new terminal or display size. Returns whether
+    /// the display fits.
+    fn lay_out(&mut self, out: &mut String, terminal: (usize, usize), display: (usize, usize)) -> bool {
+        let (columns, rows) = terminal;
+        let (width, height) = display;
+
+        self.layout = Some((terminal, display));
+        self.display_lines.clear();
+        self.panel_lines.clear();
+        out.push_str("\x1b[r\x1b[0m\x1b[2J\x1b[H");
+
+        // A line of half blocks per two rows of pixels, then a blank line and
+        // at least two lines for messages.
+        let needed = (width + PANEL_GAP + PANEL_WIDTH, height / 2 + 3);
+
+        if columns < needed.0 || rows < needed.1 {
+            out.push_str(&format!("Terminal too small, {}x{} is needed", needed.0, needed.1));
+            return false;
+        }
+
+        // Keep messages below the display, scrolling only among themselves.
+        let log_top = height / 2 + 2;
+        out.push_str(&format!("\x1b[{};{}r\x1b[{};1H", log_top, rows, log_top));
+        true
+    }
+}
+
+/// Draws the lines in `lines` that differ from `drawn` at `column`, from
+/// the top row, padded to `width` to cover what was there before.
+fn draw_changes(out: &mut String, column: usize, lines: &[String], drawn: &mut Vec<String>, width: usize) {
+    for (row, line) in lines.iter().enumerate() {
+        if drawn.get(row) != Some(line) {
+            out.push_str(&format!("\x1b[{};{}H{:<width$}", row + 1, column, line, width = width));
+        }
+    }
+
+    drawn.clear();
+    drawn.extend_from_slice(lines);
+}
+
+impl Display for TuiDisplay {
+    fn present(&mut self, env: &CPUEnvironment) {
+        if self.raw_mode.is_none() {
+            return;
+        }
+
+        let terminal = terminal_size();
+        let display = (env.display_width as usize, env.display_height as usize);
+        let mut out = String::new();
+
+        let laid_out = self.layout != Some((terminal, display));
+
+        if laid_out {
+            self.fits = self.lay_out(&mut out, terminal, display);
+        }
+
+        if !self.fits {
+            print!("{}", out);
+            let _ = io::stdout().flush();
+            return;
+        }
+
+        // Save the cursor's place among the messages, and go back there after.
+        out.push_str("\x1b7");
+
+        if self.phosphor.update(env) || laid_out {
+            let lines = terminal::half_blocks(self.phosphor.pixels(), display.0);
+            draw_changes(&mut out, 1, &lines, &mut self.display_lines, 0);
+        }
+
+        let mut panel = self.registers.clone();
+        panel.push(String::new());
+        panel.push(self.status.clone().unwrap_or_default());
+        draw_changes(&mut out, display.0 + PANEL_GAP + 1, &panel, &mut self.panel_lines, PANEL_WIDTH);
+
+        out.push_str("\x1b8");
+        print!("{}", out);
+        let _ = io::stdout().flush();
+    }
+
+    fn set_status(&mut self, status: Option<&str>) {
+        self.status = status.map(|status| status.chars().take(PANEL_WIDTH).collect());
+    }
+
+    fn show_registers(&mut self, cpu: &CPU) {
+        self.registers = terminal::register_panel(cpu);
+    }
+
+    fn set_debugging(&mut self, active: bool) {
+        if active {
+            self.leave();
+        } else if let Err(err) = self.enter() {
+            eprintln!("Failed to return to the terminal display: {}", err);
+        }
+    }
+}
+
+impl Drop for TuiDisplay {
+    fn drop(&mut self) {
+        if self.raw_mode.is_some() {
+            self.leave();
+        }
+    }
+}
+
+/// Reads keys from the terminal. Keys count as released once they stop
+/// repeating, and Ctrl+C quits.
+pub struct TuiInput {
+    keymap: Keymap,
+    held: HeldKeys,
+    start: Instant
+}
+
+fn binding_event(binding: Binding, pressed: bool) -> Event {
+    match binding {
+        Binding::Key(key) => Event::Key { key, pressed },
+        Binding::Action(action) => Event::Action { action, pressed }
+    }
+}
+
+impl Input for TuiInput {
+    fn poll(&mut self) -> Vec<Event> {
+        let mut input = Vec::new();
+        let mut buffer = [0; 64];
+
+        // Raw mode makes reads return nothing rather than wait for input.
+        while let Ok(len @ 1..) = io::stdin().read(&mut buffer) {
+            input.extend_from_slice(&buffer[..len]);
+        }
+
+        let now = self.start.elapsed();
+        let mut events = Vec::new();
+
+        for key in terminal::key_names(&input) {
+            if key == "Ctrl+C" {
+                events.push(Event::Quit);
+            } else if self.held.press(&key, now) {
+                if let Some(binding) = self.keymap.get(Device::Keyboard, &key) {
+                    events.push(binding_event(binding, true));
+                }
+            }
+        }
+
+        for key in self.held.release(now) {
+            if let Some(binding) = self.keymap.get(Device::Keyboard, &key) {
+                events.push(binding_event(binding, false));
+            }
+        }
+
+        events
+    }
+}
+
+/// Rings the terminal bell when the buzzer starts.
+pub struct TerminalBell {
+    sounding: bool
+}
+
+impl Audio for TerminalBell {
+    fn update(&mut self, sound_timer: u8, _env: &CPUEnvironment) {
+        let sounding = sound_timer > 0;
+
+        if sounding && !self.sounding {
+            print!("\x07");
+            let _ = io::stdout().flush();
+        }
+
+        self.sounding = sounding;
+    }
+}
+
+pub fn init(phosphor: Phosphor, keymap: Keymap) -> Result<(TuiDisplay, TuiInput, TerminalBell), String> {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) == 0 || libc::isatty(libc::STDOUT_FILENO) == 0 } {
+        return Err("--tui needs stdin and stdout to be a terminal".to_string());
+    }
+
+    let mut display = TuiDisplay {
+        raw_mode: None,
+        phosphor,
+        layout: None,
+        fits: false,
+        display_lines: Vec::new(),
+        panel_lines: Vec::new(),
+        registers: Vec::new(),
+        status: None
+    };
+
+    display.enter().map_err(|err| format!("failed to set up the terminal: {}", err))?;
+
+    let input = TuiInput { keymap, held: HeldKeys::new(FIRST_KEY_HOLD_TIME, KEY_HOLD_TIME), start: Instant::now() };
+
+    Ok((display, input, TerminalBell { sounding: false }))
+}
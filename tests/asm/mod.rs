@@ -0,0 +1,138 @@
+use chip8::asm::{assemble, assemble_at};
+use chip8::cpu::CPU;
+use chip8::disasm::disassemble;
+
+#[test]
+pub fn asm_instructions() {
+    let rom = assemble("
+        LD V0, 0x03
+        ADD V0, 7
+        LD V2, V1
+        DRW V0, V1, 5
+        LD [I], V2
+        LD V1-V3, [I]
+        LD I, LONG 0xBEEF
+        shr v1
+    ").unwrap();
+
+    assert_eq!(vec![
+        0x60, 0x03,
+        0x70, 0x07,
+        0x82, 0x10,
+        0xD0, 0x15,
+        0xF2, 0x55,
+        0x51, 0x33,
+        0xF0, 0x00, 0xBE, 0xEF,
+        0x81, 0x16,
+    ], rom);
+}
+
+#[test]
+pub fn asm_labels() {
+    let rom = assemble("
+        start:  LD V0, 0x00
+        loop:   ADD V0, 0x01
+                SE V0, LIMIT   ; done?
+                JP loop
+                CALL sub
+        sub:    RET
+        LIMT EQU 0x0A
+    ");
+
+    assert_eq!(Err("4:24: undefined symbol `LIMIT`".to_string()), rom.map_err(|e| e.to_string()));
+
+    let rom = assemble("
+        LIMIT EQU 0x0A
+        start:  LD V0, 0x00
+        loop:   ADD V0, 0x01
+                SE V0, LIMIT
+                JP loop
+                CALL sub
+        sub:    RET
+                JP start + 2
+    ").unwrap();
+
+    assert_eq!(vec![
+        0x60, 0x00,
+        0x70, 0x01,
+        0x30, 0x0A,
+        0x12, 0x02,
+        0x22, 0x0A,
+        0x00, 0xEE,
+        0x12, 0x02,
+    ], rom);
+}
+
+#[test]
+pub fn asm_directives() {
+    let rom = assemble_at("
+        JP code
+        digit:
+            SPRITE \"####....\"
+            SPRITE \"#..#....\"
+            DB 0b11110000, 0x90, \"ab\"
+            DW 0x1234
+        ORG 0x210
+        code:
+            LD I, digit
+    ", 0x200).unwrap();
+
+    assert_eq!(vec![
+        0x12, 0x10,
+        0xF0, 0x90,
+        0xF0, 0x90, b'a', b'b',
+        0x12, 0x34,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xA2, 0x02,
+    ], rom);
+}
+
+#[test]
+pub fn asm_errors() {
+    let err = assemble("CLS\n  FOO V0").unwrap_err();
+    assert_eq!((2, 3), (err.line, err.column));
+    assert_eq!("unknown mnemonic `FOO`", err.message);
+
+    let err = assemble("LD V0, 0x100").unwrap_err();
+    assert_eq!((1, 8), (err.line, err.column));
+
+    let err = assemble("ADD V0, DT").unwrap_err();
+    assert_eq!("invalid operands for ADD", err.message);
+
+    let err = assemble("a: CLS\na: CLS").unwrap_err();
+    assert_eq!((2, 1), (err.line, err.column));
+
+    let err = assemble("CLS\nLD V0, 0x7FFFFFFFFFFFFFFF + 1").unwrap_err();
+    assert_eq!((2, 29), (err.line, err.column));
+    assert_eq!("expression `0x7FFFFFFFFFFFFFFF + 1` overflows", err.message);
+
+    let err = assemble("BIG EQU 0x7FFFFFFFFFFFFFFF\nLD V0, BIG + BIG").unwrap_err();
+    assert_eq!((2, 14), (err.line, err.column));
+}
+
+#[test]
+pub fn asm_runs_on_cpu() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&assemble("
+            LD V0, 0x00
+        loop:
+            ADD V0, 0x01
+            SE V0, 0x0A
+            JP loop
+    ").unwrap());
+
+    cpu.run(true).unwrap();
+
+    assert_eq!(Some(0x0A), cpu.regs.v(0x0));
+}
+
+#[test]
+pub fn asm_disasm_roundtrip() {
+    let rom = vec![
+        0xA2, 0x0A, 0x60, 0x05, 0x22, 0x08, 0x12, 0x06,
+        0xD0, 0x05, 0x00, 0xEE, 0xF0, 0x90,
+    ];
+
+    let source = disassemble(&rom, 0x200).to_string();
+    assert_eq!(rom, assemble(&source).unwrap());
+}
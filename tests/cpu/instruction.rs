@@ -0,0 +1,37 @@
+use chip8::cpu::instruction::Instruction;
+use chip8::cpu::error::DecodeError;
+
+#[test]
+pub fn instruction_decode() {
+    assert_eq!(Ok(Instruction::Ld { x: 0x1, byte: 0x42 }), Instruction::decode(0x6142));
+    assert_eq!(Ok(Instruction::Drw { x: 0x0, y: 0x1, n: 0x5 }), Instruction::decode(0xD015));
+    assert_eq!(Ok(Instruction::Jp { addr: 0x202 }), Instruction::decode(0x1202));
+    assert_eq!(Ok(Instruction::Sys { addr: 0x123 }), Instruction::decode(0x0123));
+    assert_eq!(Ok(Instruction::LdILong), Instruction::decode(0xF000));
+    assert_eq!(Err(DecodeError { opcode: 0xE000 }), Instruction::decode(0xE000));
+    assert_eq!(Err(DecodeError { opcode: 0x8008 }), Instruction::decode(0x8008));
+}
+
+#[test]
+pub fn instruction_encode_roundtrip() {
+    for opcode in 0..=0xFFFF {
+        if let Ok(inst) = Instruction::decode(opcode) {
+            assert_eq!(opcode, inst.encode(), "{}", inst);
+        }
+    }
+}
+
+#[test]
+pub fn instruction_display() {
+    assert_eq!("LD V0, 0x03", Instruction::decode(0x6003).unwrap().to_string());
+    assert_eq!("JP V0, 0x202", Instruction::decode(0xB202).unwrap().to_string());
+    assert_eq!("DRW V0, V1, 0x5", Instruction::decode(0xD015).unwrap().to_string());
+    assert_eq!("LD [I], V2", Instruction::decode(0xF255).unwrap().to_string());
+    assert_eq!("SUBN V2, V3", Instruction::decode(0x8237).unwrap().to_string());
+}
+
+#[test]
+pub fn instruction_size() {
+    assert_eq!(4, Instruction::LdILong.size());
+    assert_eq!(2, Instruction::Cls.size());
+}
@@ -1,6 +1,6 @@
 use chip8::cpu::memory::Memory;
-use chip8::cpu::memory::CHIP8_MEMORY_SIZE;
-use chip8::io::chars::CHIP8_CHARACTERS;
+use chip8::cpu::memory::{CHIP8_MEMORY_SIZE, SCHIP8_CHARACTERS_ADDR};
+use chip8::io::chars::{CHIP8_CHARACTERS, SCHIP8_CHARACTERS};
 
 #[test]
 pub fn memory_in_range() {
@@ -62,4 +62,10 @@ pub fn memory_load() {
     assert_eq!(Some(1), mem.peek(0x200));
     assert_eq!(Some(2), mem.peek(0x201));
     assert_eq!(Some(3), mem.peek(0x202));
-}
\ No newline at end of file
+}
+
+#[test]
+pub fn memory_large_characters() {
+    let mem = Memory::new();
+    assert!(mem.block(SCHIP8_CHARACTERS_ADDR, 10 * 16) == Some(&SCHIP8_CHARACTERS));
+}
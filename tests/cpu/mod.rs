@@ -1,33 +1,76 @@
 mod registers;
 mod memory;
+mod quirks;
+mod schip;
+mod xochip;
+mod instruction;
+mod state;
+mod rng;
 
 use chip8::cpu::CPU;
+use chip8::cpu::error::{CPUError, MemoryAccess};
 
 #[test]
 pub fn ld_test() {
     let mut cpu = CPU::new();
-    cpu.execute(0x6142); // LD V1, 0x42
-    cpu.execute(0x6224); // LD V2, 0x24
+    cpu.execute(0x6142).unwrap(); // LD V1, 0x42
+    cpu.execute(0x6224).unwrap(); // LD V2, 0x24
     assert_eq!(Some(0x42), cpu.regs.v(0x1));
     assert_eq!(Some(0x24), cpu.regs.v(0x2));
 
-    cpu.execute(0x8210); // LD V2, V1
+    cpu.execute(0x8210).unwrap(); // LD V2, V1
     assert_eq!(Some(0x42), cpu.regs.v(0x1));
     assert_eq!(Some(0x42), cpu.regs.v(0x2));
 
-    cpu.execute(0xA666); // LD I, 0x666
+    cpu.execute(0xA666).unwrap(); // LD I, 0x666
     assert_eq!(0x666, cpu.regs.i);
 }
 
 #[test]
-#[should_panic(expected = "can't RET with an empty stack")]
 pub fn invalid_ret_test() {
     let mut cpu = CPU::new();
     cpu.mem.load_program(&[
         0x00, 0xEE
     ]);
 
-    cpu.run(true);
+    assert_eq!(Err(CPUError::StackUnderflow { addr: 0x200 }), cpu.run(true));
+}
+
+#[test]
+pub fn stack_overflow_test() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x22, 0x00, // 0200 - CALL 0x200
+    ]);
+
+    assert_eq!(Err(CPUError::StackOverflow { addr: 0x200 }), cpu.run(true));
+}
+
+#[test]
+pub fn unknown_inst_test() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x60, 0x01, // 0200 - LD V0, 0x01
+        0xE0, 0x00, // 0202 - ???
+    ]);
+
+    assert_eq!(Err(CPUError::UnknownInstruction { opcode: 0xE000, addr: 0x202 }), cpu.run(true));
+}
+
+#[test]
+pub fn memory_fault_test() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0xAF, 0xFF, // 0200 - LD I, 0xFFF
+        0xF3, 0x55, // 0202 - LD [I], V3
+    ]);
+
+    let fault = CPUError::MemoryFault { addr: 0x1000, access: MemoryAccess::Write, pc: 0x202 };
+    assert_eq!(Err(fault), cpu.run(true));
+
+    cpu.regs.pc = 0xFFF;
+    let fault = CPUError::MemoryFault { addr: 0xFFF, access: MemoryAccess::Fetch, pc: 0xFFF };
+    assert_eq!(Err(fault), cpu.step());
 }
 
 #[test]
@@ -40,7 +83,7 @@ pub fn flow_jp_test() {
         0x12, 0x02, // 0206 - JP 0x002
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0x0A), cpu.regs.v(0x0));
 
@@ -53,7 +96,7 @@ pub fn flow_jp_test() {
     ]);
 
     cpu.regs.pc = 0x200;
-    cpu.run(true);
+    cpu.run(true).unwrap();
 }
 
 #[test]
@@ -68,7 +111,7 @@ pub fn add_test() {
         0x82, 0x14, // ADD V2, V1
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0xA), cpu.regs.v(0x0));
     assert_eq!(Some(0x1), cpu.regs.v(0x2));
@@ -84,7 +127,7 @@ pub fn subtract_test() {
         0x80, 0x15, // SUB V0, V1
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0x03), cpu.regs.v(0x0));
     assert_eq!(Some(0x1), cpu.regs.v(0xF));
@@ -96,7 +139,7 @@ pub fn subtract_test() {
     ]);
 
     cpu.regs.pc = 0x200;
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0xFC), cpu.regs.v(0x2));
     assert_eq!(Some(0x1), cpu.regs.v(0xF));
@@ -113,7 +156,7 @@ pub fn shift_test() {
         0x83, 0x26, // SHR V3, V2
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0x08), cpu.regs.v(0x1));
     assert_eq!(Some(0x04), cpu.regs.v(0x3));
@@ -142,7 +185,7 @@ pub fn branch_test() {
         0x65, 0x64, // LD V5, 0x64
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0x42), cpu.regs.v(0));
     assert_eq!(Some(0x24), cpu.regs.v(1));
@@ -168,7 +211,7 @@ pub fn reg_mem_test() {
         0xF2, 0x65, // LD V2, [I]
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0x01), cpu.mem.peek(0x004));
     assert_eq!(Some(0x02), cpu.mem.peek(0x005));
@@ -189,7 +232,7 @@ pub fn ld_char_test() {
         0xF0, 0x29, // LD F, V0
     ]);
     
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(5 * 2, cpu.regs.i);
 }
@@ -203,7 +246,7 @@ pub fn bcd_test() {
         0xF3, 0x33, // LD B, V3
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     if let Some(block) = cpu.mem.block(0x400, 3) {
         assert_eq!(1, block[0]);
@@ -229,7 +272,7 @@ pub fn call_test() {
         0x00, 0xEE, // 020C - RET
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0x42), cpu.regs.v(0));
     assert_eq!(Some(0x24), cpu.regs.v(1));
@@ -242,7 +285,7 @@ pub fn step_test() {
         0x60, 0x42 // LD V0, 0x42
     ]);
 
-    cpu.step();
+    cpu.step().unwrap();
 
     assert_eq!(Some(0x42), cpu.regs.v(0));
 }
@@ -257,7 +300,28 @@ pub fn run_test() {
         0x63, 0x44  // LD V3, 0x44
     ]);
 
-    cpu.run(true);
+    cpu.run(true).unwrap();
 
     assert_eq!(Some(0x42), cpu.regs.v(0));
 }
+
+#[test]
+pub fn display_version_test() {
+    let mut cpu = CPU::new();
+    let version = cpu.env.display_version;
+
+    cpu.execute(0x00E0).unwrap(); // CLS
+    assert_ne!(version, cpu.env.display_version);
+
+    let version = cpu.env.display_version;
+    cpu.env.set_pixel(3, 4, false);
+    assert_eq!(version, cpu.env.display_version);
+
+    cpu.execute(0xD005).unwrap(); // DRW V0, V0, 5
+    assert_ne!(version, cpu.env.display_version);
+
+    let state = cpu.save_state();
+    let version = cpu.env.display_version;
+    cpu.load_state(&state).unwrap();
+    assert_ne!(version, cpu.env.display_version);
+}
@@ -0,0 +1,154 @@
+use chip8::cpu::{CPU, Interrupt};
+use chip8::cpu::quirks::Quirks;
+
+#[test]
+pub fn quirks_shift() {
+    let program = [
+        0x60, 0x04, // LD V0, 0x04
+        0x61, 0x10, // LD V1, 0x10
+        0x81, 0x0E, // SHL V1, V0
+    ];
+
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert_eq!(Some(0x08), cpu.regs.v(0x1));
+
+    let mut cpu = CPU::with_quirks(Quirks::chip48());
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert_eq!(Some(0x20), cpu.regs.v(0x1));
+}
+
+#[test]
+pub fn quirks_load_store() {
+    let program = [
+        0xA4, 0x00, // LD I, 0x400
+        0xF2, 0x55, // LD [I], V2
+    ];
+
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert_eq!(0x403, cpu.regs.i);
+
+    let mut cpu = CPU::with_quirks(Quirks::superchip());
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert_eq!(0x400, cpu.regs.i);
+}
+
+#[test]
+pub fn quirks_jump() {
+    let program = [
+        0x60, 0x02, // 0200 - LD V0, 0x02
+        0x62, 0x04, // 0202 - LD V2, 0x04
+        0xB2, 0x06, // 0204 - JP V0, 0x206
+        0x63, 0x01, // 0206 - LD V3, 0x01
+        0x64, 0x01, // 0208 - LD V4, 0x01
+        0x65, 0x01, // 020A - LD V5, 0x01
+    ];
+
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert_eq!(Some(0x0), cpu.regs.v(0x3));
+    assert_eq!(Some(0x1), cpu.regs.v(0x4));
+
+    let mut cpu = CPU::with_quirks(Quirks::chip48());
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert_eq!(Some(0x0), cpu.regs.v(0x4));
+    assert_eq!(Some(0x1), cpu.regs.v(0x5));
+}
+
+#[test]
+pub fn quirks_vf_reset() {
+    let program = [
+        0x6F, 0x05, // LD VF, 0x05
+        0x80, 0x11, // OR V0, V1
+    ];
+
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert_eq!(Some(0x5), cpu.regs.v(0xF));
+
+    let mut cpu = CPU::with_quirks(Quirks::cosmac_vip());
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert_eq!(Some(0x0), cpu.regs.v(0xF));
+}
+
+#[test]
+pub fn quirks_clipping() {
+    let program = [
+        0x60, 0x3E, // LD V0, 62
+        0xA0, 0x00, // LD I, 0x000
+        0xD0, 0x11, // DRW V0, V1, 1
+    ];
+
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert!(cpu.env.pixel(63, 0));
+    assert!(cpu.env.pixel(0, 0));
+
+    let mut cpu = CPU::with_quirks(Quirks::cosmac_vip());
+    cpu.mem.load_program(&program);
+    cpu.run(true).unwrap();
+    assert!(cpu.env.pixel(63, 0));
+    assert!(!cpu.env.pixel(0, 0));
+}
+
+#[test]
+pub fn quirks_display_wait() {
+    let mut cpu = CPU::with_quirks(Quirks::cosmac_vip());
+    cpu.mem.load_program(&[
+        0xD0, 0x01, // DRW V0, V0, 1
+        0x60, 0x01, // LD V0, 0x01
+    ]);
+
+    assert_eq!(Ok(true), cpu.step());
+    assert_eq!(Ok(false), cpu.step());
+    assert_eq!(Some(0x0), cpu.regs.v(0x0));
+
+    cpu.tick();
+    assert!(matches!(cpu.interrupt, Interrupt::None));
+    assert_eq!(Ok(true), cpu.step());
+    assert_eq!(Some(0x1), cpu.regs.v(0x0));
+}
+
+#[test]
+pub fn quirks_lores_dxy0() {
+    let program = [
+        0xA4, 0x00, // LD I, 0x400
+        0xD0, 0x00, // DRW V0, V0, 0
+    ];
+
+    let mut cpu = CPU::new();
+    cpu.mem.load(0x400, &[0xFF; 32]);
+    cpu.mem.load_program(&program);
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    assert_eq!(16 * 16, cpu.env.display.iter().filter(|p| **p != 0).count());
+
+    let mut cpu = CPU::with_quirks(Quirks::superchip());
+    cpu.mem.load(0x400, &[0xFF; 32]);
+    cpu.mem.load_program(&program);
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    assert_eq!(8 * 16, cpu.env.display.iter().filter(|p| **p != 0).count());
+    assert!(cpu.env.pixel(7, 15));
+    assert!(!cpu.env.pixel(8, 0));
+
+    cpu.execute(0x00E0).unwrap(); // CLS
+    cpu.execute(0x00FF).unwrap(); // HIGH
+    cpu.regs.pc = 0x202;
+    cpu.step().unwrap();
+    assert_eq!(16 * 16, cpu.env.display.iter().filter(|p| **p != 0).count());
+
+    let mut restored = CPU::new();
+    restored.load_state(&cpu.save_state()).unwrap();
+    assert!(restored.quirks.lores_dxy0);
+}
@@ -0,0 +1,72 @@
+use chip8::cpu::CPU;
+use chip8::cpu::quirks::Quirks;
+use chip8::cpu::rng::{RandomSource, XorShift};
+
+fn roll(cpu: &mut CPU, n: usize) -> Vec<u8> {
+    (0..n).map(|_| {
+        cpu.execute(0xC0FF).unwrap(); // RND V0, 0xFF
+        cpu.regs.v(0).unwrap()
+    }).collect()
+}
+
+#[test]
+pub fn rng_seeded() {
+    let mut a = CPU::with_seed(Quirks::default(), 1234);
+    let mut b = CPU::with_seed(Quirks::default(), 1234);
+    let mut c = CPU::with_seed(Quirks::default(), 4321);
+
+    assert_eq!(1234, a.seed());
+    assert_eq!(roll(&mut a, 32), roll(&mut b, 32));
+    assert_ne!(roll(&mut a, 32), roll(&mut c, 32));
+
+    a.reseed(1234);
+    b.reseed(1234);
+    assert_eq!(roll(&mut a, 8), roll(&mut b, 8));
+}
+
+#[test]
+pub fn rng_xorshift_sequence() {
+    // Pins the sequence so recorded runs stay reproducible across versions.
+    let mut rng = XorShift::new(1);
+    let values: Vec<u8> = (0..4).map(|_| rng.next_u8()).collect();
+    assert_eq!(vec![0x47, 0xAB, 0xB9, 0x4D], values);
+
+    let mut zero = XorShift::new(0);
+    assert_eq!(0, zero.seed());
+    assert_ne!(zero.next_u8(), zero.next_u8());
+}
+
+struct Counter {
+    seed: u64,
+    state: u64
+}
+
+impl RandomSource for Counter {
+    fn seed(&self) -> u64 { self.seed }
+    fn reseed(&mut self, seed: u64) { self.seed = seed; self.state = seed; }
+    fn next_u8(&mut self) -> u8 { self.state += 1; self.state as u8 }
+    fn state(&self) -> u64 { self.state }
+    fn set_state(&mut self, state: u64) { self.state = state; }
+}
+
+#[test]
+pub fn rng_custom_source() {
+    let mut cpu = CPU::with_rng(Quirks::default(), Box::new(Counter { seed: 5, state: 5 }));
+    assert_eq!(vec![6, 7, 8], roll(&mut cpu, 3));
+
+    cpu.execute(0xC10F).unwrap(); // RND V1, 0x0F
+    assert_eq!(Some(9), cpu.regs.v(1));
+}
+
+#[test]
+pub fn rng_save_state() {
+    let mut cpu = CPU::with_seed(Quirks::default(), 99);
+    roll(&mut cpu, 5);
+    let state = cpu.save_state();
+    let expected = roll(&mut cpu, 16);
+
+    let mut restored = CPU::new();
+    restored.load_state(&state).unwrap();
+    assert_eq!(99, restored.seed());
+    assert_eq!(expected, roll(&mut restored, 16));
+}
@@ -0,0 +1,109 @@
+use chip8::cpu::{CPU, Interrupt};
+use chip8::cpu::memory::SCHIP8_CHARACTERS_ADDR;
+
+#[test]
+pub fn schip_resolution() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x00, 0xFF, // HIGH
+    ]);
+
+    cpu.run(true).unwrap();
+
+    assert!(cpu.env.is_hires());
+    assert_eq!(128, cpu.env.display_width);
+    assert_eq!(64, cpu.env.display_height);
+    assert_eq!(128 * 64, cpu.env.display.len());
+
+    cpu.execute(0x00FE).unwrap(); // LOW
+    assert!(!cpu.env.is_hires());
+    assert_eq!(64 * 32, cpu.env.display.len());
+}
+
+#[test]
+pub fn schip_scroll() {
+    let mut cpu = CPU::new();
+    cpu.env.set_pixel(10, 10, true);
+
+    cpu.execute(0x00C3).unwrap(); // SCD 3
+    assert!(cpu.env.pixel(10, 13));
+    assert!(!cpu.env.pixel(10, 10));
+
+    cpu.execute(0x00FB).unwrap(); // SCR
+    assert!(cpu.env.pixel(14, 13));
+
+    cpu.execute(0x00FC).unwrap(); // SCL
+    cpu.execute(0x00FC).unwrap(); // SCL
+    assert!(cpu.env.pixel(6, 13));
+    assert_eq!(1, cpu.env.display.iter().filter(|p| **p != 0).count());
+}
+
+#[test]
+pub fn schip_large_sprite() {
+    let mut cpu = CPU::new();
+    cpu.mem.load(0x400, &[0xFF; 32]);
+    cpu.mem.load_program(&[
+        0x00, 0xFF, // HIGH
+        0xA4, 0x00, // LD I, 0x400
+        0xD0, 0x00, // DRW V0, V0, 0
+        0xD0, 0x00, // DRW V0, V0, 0
+    ]);
+
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+
+    assert_eq!(16 * 16, cpu.env.display.iter().filter(|p| **p != 0).count());
+    assert_eq!(Some(0), cpu.regs.v(0xF));
+
+    cpu.step().unwrap();
+
+    assert_eq!(0, cpu.env.display.iter().filter(|p| **p != 0).count());
+    assert_eq!(Some(16), cpu.regs.v(0xF));
+}
+
+#[test]
+pub fn schip_ld_large_char() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x60, 0x03, // LD V0, 3
+        0xF0, 0x30, // LD HF, V0
+    ]);
+
+    cpu.run(true).unwrap();
+
+    assert_eq!((SCHIP8_CHARACTERS_ADDR + 10 * 3) as u16, cpu.regs.i);
+}
+
+#[test]
+pub fn schip_rpl_flags() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x60, 0x11, // LD V0, 0x11
+        0x61, 0x22, // LD V1, 0x22
+        0xF1, 0x75, // LD R, V1
+        0x60, 0x00, // LD V0, 0x00
+        0x61, 0x00, // LD V1, 0x00
+        0xF1, 0x85, // LD V1, R
+    ]);
+
+    cpu.run(true).unwrap();
+
+    assert_eq!([0x11, 0x22], cpu.rpl_flags[0..2]);
+    assert_eq!(Some(0x11), cpu.regs.v(0x0));
+    assert_eq!(Some(0x22), cpu.regs.v(0x1));
+}
+
+#[test]
+pub fn schip_exit() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x00, 0xFD, // EXIT
+        0x60, 0x01, // LD V0, 0x01
+    ]);
+
+    cpu.run(true).unwrap();
+
+    assert!(matches!(cpu.interrupt, Interrupt::Exit));
+    assert_eq!(Some(0x0), cpu.regs.v(0x0));
+}
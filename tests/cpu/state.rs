@@ -0,0 +1,75 @@
+use chip8::cpu::{CPU, Interrupt};
+use chip8::cpu::error::StateError;
+use chip8::cpu::quirks::Quirks;
+use chip8::cpu::state::STATE_VERSION;
+
+#[test]
+pub fn state_round_trip() {
+    let mut cpu = CPU::xochip();
+    cpu.quirks = Quirks::chip48();
+    cpu.mem.load_program(&[
+        0x22, 0x04, // 0200 - CALL 0x204
+        0x00, 0x00, // 0202
+        0x60, 0x12, // 0204 - LD V0, 0x12
+        0xA3, 0x00, // 0206 - LD I, 0x300
+        0x00, 0xFF, // 0208 - HIGH
+        0xD0, 0x05, // 020A - DRW V0, V0, 0x5
+        0xF3, 0x0A, // 020C - LD V3, K
+    ]);
+
+    cpu.regs.dt = 0x20;
+    cpu.env.keyboard[0xA] = true;
+    cpu.rpl_flags[7] = 0x77;
+    cpu.run(false).unwrap();
+
+    let state = cpu.save_state();
+    let mut restored = CPU::new();
+    restored.load_state(&state).unwrap();
+
+    assert_eq!(state, restored.save_state());
+    assert_eq!(Quirks::chip48(), restored.quirks);
+    assert_eq!(Interrupt::AwaitKey(3), restored.interrupt);
+    assert_eq!(&[0x202], restored.stack());
+    assert_eq!(0x20E, restored.regs.pc);
+    assert_eq!(0x300, restored.regs.i);
+    assert_eq!(Some(0x12), restored.regs.v(0));
+    assert_eq!(0x20, restored.regs.dt);
+    assert!(restored.env.is_hires());
+    assert_eq!(cpu.env.display, restored.env.display);
+    assert!(restored.env.is_key_pressed(0xA));
+    assert_eq!(0x77, restored.rpl_flags[7]);
+    assert_eq!(65536, restored.mem.size());
+}
+
+#[test]
+pub fn state_restores_rng() {
+    let mut cpu = CPU::new();
+    let state = cpu.save_state();
+
+    cpu.execute(0xC0FF).unwrap(); // RND V0, 0xFF
+    cpu.execute(0xC1FF).unwrap(); // RND V1, 0xFF
+    let rolled = (cpu.regs.v(0), cpu.regs.v(1));
+
+    cpu.load_state(&state).unwrap();
+    cpu.execute(0xC0FF).unwrap(); // RND V0, 0xFF
+    cpu.execute(0xC1FF).unwrap(); // RND V1, 0xFF
+    assert_eq!(rolled, (cpu.regs.v(0), cpu.regs.v(1)));
+}
+
+#[test]
+pub fn state_errors() {
+    let mut cpu = CPU::new();
+    cpu.regs.pc = 0x300;
+    let mut state = cpu.save_state();
+
+    let mut other = CPU::new();
+    assert_eq!(Err(StateError::BadMagic), other.load_state(b"nope"));
+    assert_eq!(Err(StateError::Truncated), other.load_state(&state[..state.len() - 1]));
+
+    state[4] = 99;
+    assert_eq!(Err(StateError::UnsupportedVersion(99)), other.load_state(&state));
+
+    state[4] = STATE_VERSION - 1;
+    assert_eq!(Err(StateError::UnsupportedVersion(STATE_VERSION - 1)), other.load_state(&state));
+    assert_eq!(0x200, other.regs.pc);
+}
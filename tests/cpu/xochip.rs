@@ -0,0 +1,117 @@
+use chip8::cpu::CPU;
+use chip8::cpu::memory::XOCHIP_MEMORY_SIZE;
+
+#[test]
+pub fn xochip_memory_size() {
+    let cpu = CPU::xochip();
+    assert_eq!(XOCHIP_MEMORY_SIZE, cpu.mem.size());
+    assert!(cpu.mem.in_range(0xFFFF));
+}
+
+#[test]
+pub fn xochip_long_ld_i() {
+    let mut cpu = CPU::xochip();
+    cpu.mem.load_program(&[
+        0xF0, 0x00, 0xBE, 0xEF, // 0200 - LD I, 0xBEEF
+        0x60, 0x01,             // 0204 - LD V0, 0x01
+    ]);
+
+    cpu.run(true).unwrap();
+
+    assert_eq!(0xBEEF, cpu.regs.i);
+    assert_eq!(Some(0x01), cpu.regs.v(0x0));
+}
+
+#[test]
+pub fn xochip_skip_long_ld_i() {
+    let mut cpu = CPU::xochip();
+    cpu.mem.load_program(&[
+        0x30, 0x00,             // 0200 - SE V0, 0x00
+        0xF0, 0x00, 0x12, 0x34, // 0202 - LD I, 0x1234
+        0x61, 0x01,             // 0206 - LD V1, 0x01
+    ]);
+
+    cpu.run(true).unwrap();
+
+    assert_eq!(0x0, cpu.regs.i);
+    assert_eq!(Some(0x01), cpu.regs.v(0x1));
+}
+
+#[test]
+pub fn xochip_register_range() {
+    let mut cpu = CPU::xochip();
+    cpu.mem.load_program(&[
+        0x61, 0x01, // LD V1, 0x01
+        0x62, 0x02, // LD V2, 0x02
+        0x63, 0x03, // LD V3, 0x03
+        0xA4, 0x00, // LD I, 0x400
+        0x51, 0x32, // LD [I], V1-V3
+        0xA4, 0x10, // LD I, 0x410
+        0x53, 0x12, // LD [I], V3-V1
+        0x55, 0x73, // LD V5-V7, [I]
+    ]);
+
+    cpu.run(true).unwrap();
+
+    assert_eq!(Some(&[1, 2, 3][..]), cpu.mem.block(0x400, 3));
+    assert_eq!(Some(&[3, 2, 1][..]), cpu.mem.block(0x410, 3));
+    assert_eq!(0x410, cpu.regs.i);
+    assert_eq!(Some(0x3), cpu.regs.v(0x5));
+    assert_eq!(Some(0x1), cpu.regs.v(0x7));
+}
+
+#[test]
+pub fn xochip_planes() {
+    let mut cpu = CPU::xochip();
+    cpu.mem.load(0x400, &[0x80, 0x80]);
+    cpu.mem.load_program(&[
+        0xF3, 0x01, // PLANE 3
+        0xA4, 0x00, // LD I, 0x400
+        0xD0, 0x01, // DRW V0, V0, 1
+        0xF2, 0x01, // PLANE 2
+        0x00, 0xE0, // CLS
+    ]);
+
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+
+    assert_eq!(3, cpu.env.colour(0, 0));
+
+    cpu.run(true).unwrap();
+
+    assert_eq!(1, cpu.env.colour(0, 0));
+    assert!(cpu.env.pixel(0, 0));
+    assert!(!cpu.env.plane_pixel(1, 0, 0));
+}
+
+#[test]
+pub fn xochip_audio() {
+    let mut cpu = CPU::xochip();
+    cpu.mem.load(0x400, &[0xAA; 16]);
+    cpu.mem.load_program(&[
+        0xA4, 0x00, // LD I, 0x400
+        0xF0, 0x02, // LD AUDIO, [I]
+        0x60, 0x70, // LD V0, 112
+        0xF0, 0x3A, // LD PITCH, V0
+    ]);
+
+    assert_eq!(4000.0, cpu.env.audio_frequency());
+
+    cpu.run(true).unwrap();
+
+    assert_eq!([0xAA; 16], cpu.env.audio_pattern);
+    assert_eq!(112, cpu.env.audio_pitch);
+    assert_eq!(8000.0, cpu.env.audio_frequency());
+}
+
+#[test]
+pub fn xochip_scroll_up() {
+    let mut cpu = CPU::xochip();
+    cpu.env.set_pixel(5, 5, true);
+
+    cpu.execute(0x00D2).unwrap(); // SCU 2
+
+    assert!(cpu.env.pixel(5, 3));
+    assert!(!cpu.env.pixel(5, 5));
+}
@@ -0,0 +1,161 @@
+use chip8::cpu::CPU;
+use chip8::cpu::error::MemoryAccess;
+use chip8::cpu::instruction::Instruction;
+use chip8::cpu::quirks::Quirks;
+use chip8::debug::{accesses, Compare, Condition, Debugger, Register, Stop, Watch};
+
+fn loop_cpu() -> CPU {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x60, 0x00, // 0200 - LD V0, 0x00
+        0x70, 0x01, // 0202 - ADD V0, 0x01
+        0x30, 0x0A, // 0204 - SE V0, 0x0A
+        0x12, 0x02, // 0206 - JP 0x202
+        0x00, 0xFD, // 0208 - EXIT
+    ]);
+    cpu
+}
+
+#[test]
+pub fn debug_breakpoint() {
+    let mut cpu = loop_cpu();
+    let mut dbg = Debugger::new();
+    dbg.add_breakpoint(0x204, None);
+
+    assert_eq!(None, dbg.check(&cpu));
+    cpu.step().unwrap();
+    cpu.step().unwrap();
+    assert_eq!(Some(Stop::Breakpoint(0x204)), dbg.check(&cpu));
+
+    dbg.resume(&cpu);
+    assert_eq!(None, dbg.check(&cpu));
+}
+
+#[test]
+pub fn debug_conditional_breakpoint() {
+    let mut cpu = loop_cpu();
+    let mut dbg = Debugger::new();
+    let condition = Condition { reg: Register::V(0), cmp: Compare::Eq, value: 5 };
+    dbg.add_breakpoint(0x204, Some(condition));
+
+    loop {
+        if let Some(stop) = dbg.check(&cpu) {
+            assert_eq!(Stop::Breakpoint(0x204), stop);
+            break;
+        }
+
+        cpu.step().unwrap();
+    }
+
+    assert_eq!(Some(5), cpu.regs.v(0));
+}
+
+#[test]
+pub fn debug_watchpoint() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0xA4, 0x00, // LD I, 0x400
+        0xF2, 0x55, // LD [I], V2
+    ]);
+
+    let mut dbg = Debugger::new();
+    dbg.add_watchpoint(0x402, 1, Watch::Read);
+    assert_eq!(None, dbg.check(&cpu));
+
+    cpu.step().unwrap();
+    assert_eq!(None, dbg.check(&cpu));
+
+    dbg.add_watchpoint(0x402, 1, Watch::Write);
+    assert_eq!(Some(Stop::Watchpoint { addr: 0x402, access: MemoryAccess::Write }), dbg.check(&cpu));
+}
+
+#[test]
+pub fn debug_large_sprite_accesses() {
+    let drw = Instruction::Drw { x: 0, y: 0, n: 0 };
+
+    let mut cpu = CPU::new();
+    cpu.regs.i = 0x400;
+    assert_eq!(vec![(0x400, 32, MemoryAccess::Read)], accesses(&cpu, &drw));
+
+    let mut cpu = CPU::with_quirks(Quirks::superchip());
+    cpu.regs.i = 0x400;
+    assert_eq!(vec![(0x400, 16, MemoryAccess::Read)], accesses(&cpu, &drw));
+
+    cpu.execute(0x00FF).unwrap(); // HIGH
+    assert_eq!(vec![(0x400, 32, MemoryAccess::Read)], accesses(&cpu, &drw));
+}
+
+#[test]
+pub fn debug_step_over_and_out() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x22, 0x06, // 0200 - CALL 0x206
+        0x61, 0x01, // 0202 - LD V1, 0x01
+        0x00, 0xFD, // 0204 - EXIT
+        0x60, 0x01, // 0206 - LD V0, 0x01
+        0x60, 0x02, // 0208 - LD V0, 0x02
+        0x00, 0xEE, // 020A - RET
+    ]);
+
+    let mut dbg = Debugger::new();
+
+    assert_eq!(Stop::Step, dbg.step_over(&mut cpu));
+    assert_eq!(0x202, cpu.regs.pc);
+    assert_eq!(Some(2), cpu.regs.v(0));
+
+    cpu.regs.pc = 0x200;
+    dbg.step(&mut cpu);
+    assert_eq!(0x206, cpu.regs.pc);
+    assert_eq!(Stop::Step, dbg.step_out(&mut cpu));
+    assert_eq!(0x202, cpu.regs.pc);
+    assert!(cpu.stack().is_empty());
+
+    cpu.regs.pc = 0x200;
+    dbg.add_breakpoint(0x208, None);
+    assert_eq!(Stop::Breakpoint(0x208), dbg.step_over(&mut cpu));
+}
+
+#[test]
+pub fn debug_commands() {
+    let mut cpu = loop_cpu();
+    let mut dbg = Debugger::new();
+
+    let response = dbg.command(&mut cpu, "b 0x204 V0 >= 3");
+    assert_eq!("breakpoint at 0204", response.text);
+    assert_eq!("break 0204 if V0 >= 0x3", dbg.command(&mut cpu, "bl").text);
+
+    let response = dbg.command(&mut cpu, "s 20");
+    assert!(response.text.starts_with("breakpoint at 0204"));
+    assert_eq!(Some(3), cpu.regs.v(0));
+
+    assert!(dbg.command(&mut cpu, "r").text.contains("V0=03"));
+    assert!(dbg.command(&mut cpu, "l").text.starts_with("=> 0204  SE V0, 0x0A"));
+    assert_eq!("0200  60 00 70 01", dbg.command(&mut cpu, "m 0x200 4").text);
+    assert_eq!("stack is empty", dbg.command(&mut cpu, "bt").text);
+
+    let response = dbg.command(&mut cpu, "c");
+    assert!(response.resume);
+    assert_eq!(None, dbg.check(&cpu));
+
+    dbg.command(&mut cpu, "d 0x204");
+    assert!(dbg.breakpoints().is_empty());
+    assert!(dbg.command(&mut cpu, "bogus").text.starts_with("unknown command"));
+}
+
+#[test]
+pub fn debug_addresses_out_of_range() {
+    let mut cpu = loop_cpu();
+    let mut dbg = Debugger::new();
+
+    for command in ["b 0x1000", "b 0xffffffffffffffff", "d 0x1000", "watch 18446744073709551615 2", "m 0x1000", "l 0x1000"] {
+        assert!(dbg.command(&mut cpu, command).text.contains("outside memory"), "{}", command);
+    }
+
+    assert!(dbg.command(&mut cpu, "b 0x10000").text.contains("outside memory"));
+    assert!(dbg.command(&mut cpu, "b 0x200 V0 == 0x10000").text.starts_with("invalid value"));
+    assert!(dbg.breakpoints().is_empty());
+
+    assert_eq!("watching 0FFE..1000 (Access)", dbg.command(&mut cpu, "watch 0xFFE 0xFFFFFFFFFFFFFFFF").text);
+    assert_eq!("0FF0  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00", dbg.command(&mut cpu, "m 0xFF0 0xFFFFFFFFFFFFFFFF").text);
+    assert_eq!(None, dbg.check(&cpu));
+}
@@ -0,0 +1,57 @@
+use chip8::cpu::instruction::Instruction;
+use chip8::disasm::{disassemble, Item};
+
+#[test]
+pub fn disasm_code() {
+    let dis = disassemble(&[
+        0x60, 0x00, // 0200 - LD V0, 0x00
+        0x70, 0x01, // 0202 - ADD V0, 0x01
+        0x30, 0x0A, // 0204 - SE V0, 0x0A
+        0x12, 0x02, // 0206 - JP 0x202
+        0x00, 0xFD, // 0208 - EXIT
+    ], 0x200);
+
+    assert_eq!(5, dis.lines.len());
+    assert_eq!(Item::Code(Instruction::Add { x: 0x0, byte: 0x01 }), dis.lines[1].item);
+    assert_eq!(vec![0x12, 0x02], dis.lines[3].bytes);
+    assert_eq!(Some(&"L202".to_string()), dis.labels.get(&0x202));
+
+    let text = dis.to_string();
+    assert!(text.contains("L202:\n"));
+    assert!(text.contains("    LD V0, 0x00"));
+    assert!(text.contains("JP L202"));
+    assert!(text.contains("; 0206  12 02"));
+}
+
+#[test]
+pub fn disasm_sprite_and_data() {
+    let dis = disassemble(&[
+        0xA2, 0x06, // 0200 - LD I, 0x206
+        0x12, 0x04, // 0202 - JP 0x204
+        0x12, 0x04, // 0204 - JP 0x204
+        0xF0, 0x90, // 0206 - sprite
+    ], 0x200);
+
+    assert_eq!(Item::Sprite(0xF0), dis.lines[3].item);
+    assert_eq!(Item::Sprite(0x90), dis.lines[4].item);
+
+    let text = dis.to_string();
+    assert!(text.contains("LD I, S206"));
+    assert!(text.contains("S206:\n    DB 0b11110000"));
+    assert!(text.contains("####...."));
+
+    let dis = disassemble(&[0x00, 0xEE, 0x12, 0x34], 0x200);
+    assert_eq!(Item::Data(vec![0x12, 0x34]), dis.lines[1].item);
+    assert!(dis.to_string().contains("DB 0x12, 0x34"));
+}
+
+#[test]
+pub fn disasm_long_ld_i() {
+    let dis = disassemble(&[
+        0xF0, 0x00, 0xBE, 0xEF, // 0200 - LD I, 0xBEEF
+        0x00, 0xFD,             // 0204 - EXIT
+    ], 0x200);
+
+    assert_eq!(2, dis.lines.len());
+    assert!(dis.to_string().contains("LD I, LONG 0xBEEF"));
+}
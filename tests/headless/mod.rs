@@ -0,0 +1,203 @@
+use std::time::Duration;
+
+use chip8::cpu::{CPU, CPUEnvironment};
+use chip8::cpu::error::CPUError;
+use chip8::headless::{self, Limit, Options};
+use chip8::io::{screenshot, Audio, Clock, Display, ManualClock};
+use chip8::movie::InputEvent;
+
+#[test]
+pub fn headless_script() {
+    let script = "# wait for the title screen\n30 +5 +a\n\n45 -5   # let go\n";
+    let events = headless::parse_script(script).unwrap();
+
+    assert_eq!(vec![
+        InputEvent { frame: 30, keys: 0x0420 },
+        InputEvent { frame: 45, keys: 0x0400 }
+    ], events);
+
+    assert_eq!(1, headless::parse_script("x +1").unwrap_err().line);
+    assert_eq!(2, headless::parse_script("5 +1\n3 -1").unwrap_err().line);
+    assert_eq!(1, headless::parse_script("5 1").unwrap_err().line);
+    assert_eq!(1, headless::parse_script("5 +G").unwrap_err().line);
+}
+
+#[test]
+pub fn headless_limits() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x70, 0x01, // 0200 - ADD V0, 0x01
+        0x12, 0x00, // 0202 - JP 0x200
+    ]);
+
+    let options = Options { limit: Limit::Frames(3), instructions_per_frame: 4, input: Vec::new() };
+    let report = headless::run(&mut cpu, &options);
+    assert_eq!(3, report.frames);
+    assert_eq!(12, report.instructions);
+    assert_eq!(Some(6), cpu.regs.v(0));
+
+    let options = Options { limit: Limit::Instructions(7), instructions_per_frame: 4, input: Vec::new() };
+    let report = headless::run(&mut CPU::new(), &options);
+    assert_eq!(2, report.frames);
+    assert_eq!(7, report.instructions);
+}
+
+#[test]
+pub fn headless_blocked() {
+    let program = [
+        0x70, 0x01, // 0200 - ADD V0, 0x01
+        0xF1, 0x0A, // 0202 - LD V1, K
+        0x12, 0x00, // 0204 - JP 0x200
+    ];
+
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&program);
+
+    let options = Options { limit: Limit::Instructions(10), ..Options::default() };
+    let report = headless::run(&mut cpu, &options);
+    assert!(report.blocked);
+    assert_eq!(2, report.instructions);
+    assert_eq!(0, report.frames);
+    assert!(headless::registers_json(&cpu, &report).contains("\"blocked\": true"));
+
+    // Pressing a key lets it get further, until it waits again.
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&program);
+
+    let options = Options {
+        limit: Limit::Instructions(10),
+        input: vec![InputEvent { frame: 5, keys: 0x0001 }, InputEvent { frame: 6, keys: 0 }],
+        ..Options::default()
+    };
+
+    let report = headless::run(&mut cpu, &options);
+    assert!(report.blocked);
+    assert_eq!(5, report.instructions);
+
+    // Frame limits run out the frames.
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&program);
+
+    let options = Options { limit: Limit::Frames(20), ..Options::default() };
+    let report = headless::run(&mut cpu, &options);
+    assert!(!report.blocked);
+    assert_eq!(20, report.frames);
+}
+
+#[test]
+pub fn headless_input_and_exit() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0xF3, 0x0A, // 0200 - LD V3, K
+        0xF3, 0x29, // 0202 - LD F, V3
+        0xD0, 0x05, // 0204 - DRW V0, V0, 0x5
+        0x00, 0xFD, // 0206 - EXIT
+    ]);
+
+    let options = Options {
+        limit: Limit::Frames(100),
+        input: vec![InputEvent { frame: 10, keys: 0x0002 }],
+        ..Options::default()
+    };
+
+    let report = headless::run(&mut cpu, &options);
+    assert!(report.exited);
+    assert_eq!(None, report.error);
+    assert_eq!(10, report.frames);
+    assert_eq!(Some(1), cpu.regs.v(3));
+
+    let text = screenshot::text(&cpu.env);
+    let rows: Vec<&str> = text.lines().collect();
+    assert_eq!(32, rows.len());
+    assert_eq!("..#.....", &rows[0][..8]);
+    assert_eq!(".##.....", &rows[1][..8]);
+    assert_eq!(".###....", &rows[4][..8]);
+
+    let json = headless::registers_json(&cpu, &report);
+    assert!(json.contains("\"v\": [0, 0, 0, 1, 0"));
+    assert!(json.contains("\"exited\": true"));
+    assert!(json.contains("\"error\": null"));
+}
+
+#[test]
+pub fn headless_error() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[0x00, 0xEE]); // RET
+
+    let report = headless::run(&mut cpu, &Options::default());
+    assert_eq!(Some(CPUError::StackUnderflow { addr: 0x200 }), report.error);
+
+    let json = headless::registers_json(&cpu, &report);
+    assert!(json.contains("\"error\": \"can't RET with an empty stack at 0200\""));
+}
+
+#[test]
+pub fn headless_png() {
+    let mut cpu = CPU::new();
+    cpu.env.set_pixel(0, 0, true);
+
+    let png = headless::display_png(&cpu.env);
+    assert_eq!(b"\x89PNG\r\n\x1a\n", &png[..8]);
+    assert_eq!(b"IHDR", &png[12..16]);
+    assert_eq!(&[0, 0, 0, 64, 0, 0, 0, 32, 8, 2], &png[16..26]);
+    assert_eq!(b"IEND", &png[png.len() - 8..png.len() - 4]);
+
+    // IDAT: zlib header, one stored block holding 32 rows of a filter byte and 64 RGB pixels.
+    let raw_len = 32 * (1 + 64 * 3);
+    assert_eq!(b"IDAT", &png[37..41]);
+    assert_eq!(&[0x78, 0x01, 0x01], &png[41..44]);
+    assert_eq!(&(raw_len as u16).to_le_bytes(), &png[44..46]);
+    assert_eq!(&[0, 0xFF, 0xFF, 0xFF, 0], &png[48..53]);
+
+    assert_eq!(screenshot::text(&cpu.env), headless::display_text(&cpu.env));
+}
+
+struct FrameCounter {
+    frames: u32,
+    lit: Vec<usize>
+}
+
+impl Display for FrameCounter {
+    fn present(&mut self, env: &CPUEnvironment) {
+        self.frames += 1;
+        self.lit.push(env.display.iter().filter(|pix| **pix != 0).count());
+    }
+}
+
+struct Buzzer {
+    sounding: Vec<bool>
+}
+
+impl Audio for Buzzer {
+    fn update(&mut self, sound_timer: u8, _env: &CPUEnvironment) {
+        self.sounding.push(sound_timer > 0);
+    }
+}
+
+#[test]
+pub fn headless_frontend() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x60, 0x02, // 0200 - LD V0, 0x02
+        0xF0, 0x18, // 0202 - LD ST, V0
+        0xD1, 0x11, // 0204 - DRW V1, V1, 0x1
+        0x12, 0x06, // 0206 - JP 0x206
+    ]);
+
+    let mut display = FrameCounter { frames: 0, lit: Vec::new() };
+    let mut audio = Buzzer { sounding: Vec::new() };
+    let options = Options { limit: Limit::Frames(4), instructions_per_frame: 2, input: Vec::new() };
+    headless::run_with(&mut cpu, &options, &mut display, &mut audio);
+
+    assert_eq!(4, display.frames);
+    assert_eq!(vec![0, 4, 4, 4], display.lit);
+    assert_eq!(vec![true, false, false, false], audio.sounding);
+}
+
+#[test]
+pub fn headless_manual_clock() {
+    let mut clock = ManualClock::new();
+    clock.sleep(Duration::from_millis(16));
+    clock.sleep(Duration::from_millis(1));
+    assert_eq!(Duration::from_millis(17), clock.now());
+}
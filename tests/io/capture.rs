@@ -0,0 +1,194 @@
+use chip8::cpu::CPUEnvironment;
+use chip8::io::capture::{Capture, CaptureFormat, CaptureThread};
+use chip8::io::palette::Palette;
+use chip8::io::screenshot;
+
+#[test]
+pub fn capture_raw() {
+    let mut env = CPUEnvironment::new(64, 32);
+    env.set_pixel(1, 0, true);
+
+    let mut capture = Capture::new(Vec::new(), CaptureFormat::Raw, Palette::default(), 2).unwrap();
+    assert_eq!((128, 64), capture.size());
+
+    capture.add_frame(&env).unwrap();
+    env.set_hires(true);
+    env.set_pixel(1, 0, true);
+    capture.add_frame(&env).unwrap();
+    assert_eq!(2, capture.frames());
+
+    let raw = capture.finish().unwrap();
+    let frame_len = 128 * 64 * 3;
+    let pixel = |frame: usize, x: usize, y: usize| &raw[frame * frame_len + (y * 128 + x) * 3..][..3];
+
+    assert_eq!(2 * frame_len, raw.len());
+    // Lores pixels are 2x2.
+    assert_eq!(&[0, 0, 0], pixel(0, 1, 0));
+    assert_eq!(&[0xFF, 0xFF, 0xFF], pixel(0, 2, 0));
+    assert_eq!(&[0xFF, 0xFF, 0xFF], pixel(0, 3, 1));
+    assert_eq!(&[0, 0, 0], pixel(0, 4, 0));
+    assert_eq!(&[0xFF, 0xFF, 0xFF], pixel(1, 1, 0));
+    assert_eq!(&[0, 0, 0], pixel(1, 2, 0));
+}
+
+/// Decodes the LZW data of a GIF image, for a minimum code size of 2.
+fn lzw_decode(data: &[u8]) -> Vec<u8> {
+    let (clear, end) = (4, 5);
+    let mut table: Vec<Vec<u8>> = Vec::new();
+    let mut code_size = 3;
+    let mut previous: Option<usize> = None;
+    let mut out = Vec::new();
+    let (mut bits, mut len, mut bytes) = (0u32, 0, data.iter());
+
+    loop {
+        while len < code_size {
+            bits |= (*bytes.next().expect("Missing end code") as u32) << len;
+            len += 8;
+        }
+
+        let code = (bits & ((1 << code_size) - 1)) as usize;
+        bits >>= code_size;
+        len -= code_size;
+
+        if code == clear {
+            table = (0..4).map(|i| vec![i]).chain(vec![Vec::new(), Vec::new()]).collect();
+            code_size = 3;
+            previous = None;
+            continue;
+        } else if code == end {
+            return out;
+        }
+
+        let entry = match (table.get(code), previous) {
+            (Some(entry), _) => entry.clone(),
+            (None, Some(p)) if code == table.len() => {
+                let mut entry = table[p].clone();
+                entry.push(table[p][0]);
+                entry
+            },
+            _ => panic!("Invalid code {}", code)
+        };
+
+        if let Some(p) = previous {
+            let mut new = table[p].clone();
+            new.push(entry[0]);
+            table.push(new);
+
+            if table.len() == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        }
+
+        out.extend_from_slice(&entry);
+        previous = Some(code);
+    }
+}
+
+#[test]
+pub fn capture_gif() {
+    let mut env = CPUEnvironment::new(64, 32);
+    env.set_pixel(1, 0, true);
+
+    let palette = Palette::parse("000000,FFFFFF,FF0000,00FF00").unwrap();
+    let mut capture = Capture::new(Vec::new(), CaptureFormat::Gif, palette, 2).unwrap();
+
+    // Repeated frames lengthen the first one, 3 frames at 60 Hz being 5cs.
+    for _ in 0..3 {
+        capture.add_frame(&env).unwrap();
+    }
+
+    for y in 0..32 {
+        for x in 0..64 {
+            env.set_pixel(x, y, (x * 7 + y * 3) % 5 < 2);
+        }
+    }
+
+    capture.add_frame(&env).unwrap();
+    let gif = capture.finish().unwrap();
+
+    assert_eq!(b"GIF89a", &gif[..6]);
+    assert_eq!(&[128, 0, 64, 0, 0xF1, 0, 0], &gif[6..13]);
+    assert_eq!(&[0, 0, 0, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0, 0xFF, 0], &gif[13..25]);
+    assert_eq!(b"NETSCAPE2.0", &gif[28..39]);
+    assert_eq!(Some(&0x3B), gif.last());
+
+    let mut frames = Vec::new();
+    let mut rest = &gif[44..];
+
+    while rest[0] == 0x21 {
+        assert_eq!(&[0x21, 0xF9, 0x04, 0x00], &rest[..4]);
+        let delay = u16::from_le_bytes([rest[4], rest[5]]);
+        assert_eq!(&[0x2C, 0, 0, 0, 0, 128, 0, 64, 0, 0x00, 2], &rest[8..19]);
+
+        let mut data = Vec::new();
+        rest = &rest[19..];
+
+        while rest[0] != 0 {
+            let len = rest[0] as usize;
+            data.extend_from_slice(&rest[1..=len]);
+            rest = &rest[len + 1..];
+        }
+
+        frames.push((delay, lzw_decode(&data)));
+        rest = &rest[1..];
+    }
+
+    assert_eq!(&[0x3B], rest);
+    assert_eq!(2, frames.len());
+    assert_eq!(5, frames[0].0);
+    assert_eq!(2, frames[1].0);
+
+    for (_, indices) in &frames {
+        assert_eq!(128 * 64, indices.len());
+    }
+
+    for y in 0..64 {
+        for x in 0..128 {
+            assert_eq!((x == 2 || x == 3) && y < 2, frames[0].1[y * 128 + x] == 1);
+            assert_eq!(env.colour(x as u32 / 2, y as u32 / 2), frames[1].1[y * 128 + x]);
+        }
+    }
+}
+
+#[test]
+pub fn capture_matches_screenshots() {
+    let mut env = CPUEnvironment::new(64, 32);
+    env.set_hires(true);
+    env.set_pixel(0, 0, true);
+
+    for scale in 1..=3 {
+        let capture = Capture::new(Vec::new(), CaptureFormat::Raw, Palette::default(), scale).unwrap();
+        let png = screenshot::image_png(&env, &Palette::default(), scale);
+        let (width, height) = screenshot::image_size(scale);
+
+        assert_eq!((64 * scale, 32 * scale), (width, height));
+        assert_eq!((width, height), capture.size());
+        assert_eq!(&width.to_be_bytes(), &png[16..20]);
+        assert_eq!(&height.to_be_bytes(), &png[20..24]);
+    }
+
+    // Hires pixels are half the size of lores ones, rounded to the nearest pixel.
+    let display = [1, 0, 0, 1, 2, 0, 0, 3];
+    assert_eq!(vec![1, 0, 0, 1, 2, 0, 0, 3], screenshot::resize(&display, 4, 4, 2));
+    assert_eq!(vec![1, 1, 0, 0, 0, 0, 1, 1, 2, 2, 0, 0, 0, 0, 3, 3], screenshot::resize(&display, 4, 8, 2));
+    assert_eq!(vec![1, 0, 2, 0], screenshot::resize(&display, 4, 2, 2));
+    assert_eq!(vec![1, 1, 0, 2, 2, 3], screenshot::resize(&[1, 0, 2, 3], 2, 3, 2));
+}
+
+#[test]
+pub fn capture_thread() {
+    let mut env = CPUEnvironment::new(64, 32);
+    let capture = Capture::new(Vec::new(), CaptureFormat::Raw, Palette::default(), 1).unwrap();
+    let mut thread = CaptureThread::spawn(capture);
+    assert_eq!((64, 32), thread.size());
+
+    thread.add_frame(&env).unwrap();
+    env.set_pixel(5, 3, true);
+    thread.add_frame(&env).unwrap();
+    assert_eq!(2, thread.frames());
+
+    let raw = thread.finish().unwrap();
+    assert_eq!(2 * 64 * 32 * 3, raw.len());
+    assert!(raw[..64 * 32 * 3].iter().all(|b| *b == 0));
+    assert_eq!(&[0xFF, 0xFF, 0xFF], &raw[(64 * 32 + 3 * 64 + 5) * 3..][..3]);
+}
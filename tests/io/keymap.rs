@@ -0,0 +1,82 @@
+use chip8::io::Action;
+use chip8::io::keymap::{Binding, Device, Keymap};
+
+#[test]
+pub fn keymap_default() {
+    let keymap = Keymap::default();
+
+    assert_eq!(Some(Binding::Key(0x5)), keymap.get(Device::Keyboard, "W"));
+    assert_eq!(Some(Binding::Key(0xA)), keymap.get(Device::Keyboard, "y"));
+    assert_eq!(Some(Binding::Action(Action::SaveState)), keymap.get(Device::Keyboard, "F5"));
+    assert_eq!(None, keymap.get(Device::Keyboard, "Return"));
+    assert_eq!(vec!["y", "z"], keymap.keys(Device::Keyboard, Binding::Key(0xA)));
+    assert_eq!(vec!["dpup", "lefty-"], keymap.keys(Device::Gamepad, Binding::Key(0x2)));
+    assert_eq!(Some(Binding::Action(Action::Pause)), keymap.get(Device::Gamepad, "start"));
+    assert_eq!(None, keymap.get(Device::Gamepad, "W"));
+}
+
+#[test]
+pub fn keymap_parse() {
+    let text = "
+        # comment
+        [keys]
+        5 = Up, W
+        a = Space
+        pause = Return
+        screenshot =
+
+        [rom pong.ch8]
+        1 = Up
+
+        [rom other.ch8]
+        2 = Left
+    ";
+
+    let keymap = Keymap::parse(text, Some("pong.ch8")).unwrap();
+    assert_eq!(Some(Binding::Key(0x1)), keymap.get(Device::Keyboard, "Up"));
+    assert_eq!(Some(Binding::Key(0x5)), keymap.get(Device::Keyboard, "W"));
+    assert_eq!(Some(Binding::Key(0xA)), keymap.get(Device::Keyboard, "space"));
+    assert_eq!(None, keymap.get(Device::Keyboard, "Z"));
+    assert_eq!(None, keymap.get(Device::Keyboard, "Left"));
+    assert_eq!(Some(Binding::Action(Action::Pause)), keymap.get(Device::Keyboard, "Return"));
+    assert_eq!(None, keymap.get(Device::Keyboard, "P"));
+    assert!(keymap.keys(Device::Keyboard, Binding::Action(Action::Screenshot)).is_empty());
+
+    let keymap = Keymap::parse(text, None).unwrap();
+    assert_eq!(Some(Binding::Key(0x5)), keymap.get(Device::Keyboard, "Up"));
+}
+
+#[test]
+pub fn keymap_gamepad() {
+    let text = "
+        [keys]
+        5 = Space
+
+        [gamepad]
+        5 = B, rightshoulder
+        pause =
+
+        [gamepad rom pong.ch8]
+        1 = dpup, lefty-
+    ";
+
+    let keymap = Keymap::parse(text, Some("pong.ch8")).unwrap();
+    assert_eq!(Some(Binding::Key(0x5)), keymap.get(Device::Keyboard, "space"));
+    assert_eq!(Some(Binding::Key(0x5)), keymap.get(Device::Gamepad, "b"));
+    assert_eq!(Some(Binding::Key(0x5)), keymap.get(Device::Gamepad, "rightshoulder"));
+    assert_eq!(None, keymap.get(Device::Gamepad, "a"));
+    assert_eq!(None, keymap.get(Device::Gamepad, "start"));
+    assert_eq!(Some(Binding::Key(0x1)), keymap.get(Device::Gamepad, "LeftY-"));
+    assert_eq!(vec!["dpdown", "lefty+"], keymap.keys(Device::Gamepad, Binding::Key(0x8)));
+    assert_eq!(Some(Binding::Key(0x2)), Keymap::parse(text, None).unwrap().get(Device::Gamepad, "dpup"));
+
+    assert!(Keymap::parse("[gamepad keys]", None).is_err());
+}
+
+#[test]
+pub fn keymap_errors() {
+    assert_eq!(2, Keymap::parse("[keys]\nG = A", None).unwrap_err().line);
+    assert_eq!(1, Keymap::parse("5 = A", None).unwrap_err().line);
+    assert_eq!(1, Keymap::parse("[players]", None).unwrap_err().line);
+    assert_eq!(2, Keymap::parse("[keys]\n5 A", None).unwrap_err().line);
+}
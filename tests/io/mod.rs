@@ -0,0 +1,7 @@
+mod sound;
+mod palette;
+mod keymap;
+mod phosphor;
+mod screenshot;
+mod capture;
+mod terminal;
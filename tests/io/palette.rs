@@ -0,0 +1,27 @@
+use chip8::io::palette::{Palette, PALETTE_NAMES};
+
+#[test]
+pub fn palette_names() {
+    for name in PALETTE_NAMES.iter() {
+        assert!(Palette::from_name(name).is_some(), "{}", name);
+    }
+
+    assert_eq!(Some(Palette::default()), Palette::from_name("Default"));
+    assert_eq!(None, Palette::from_name("purple"));
+    assert_eq!((0, 0, 0), Palette::default().colour(0));
+    assert_eq!((255, 255, 255), Palette::default().colour(1));
+}
+
+#[test]
+pub fn palette_custom() {
+    assert_eq!(Some(Palette::default()), Palette::parse("#000000, #FFFFFF"));
+    assert_eq!(Palette::from_name("amber"), Palette::parse("amber"));
+
+    let palette = Palette::parse("102030,405060,708090,a0b0c0").unwrap();
+    assert_eq!([(0x10, 0x20, 0x30), (0x40, 0x50, 0x60), (0x70, 0x80, 0x90), (0xA0, 0xB0, 0xC0)], palette.colours);
+
+    assert_eq!(None, Palette::parse("000000"));
+    assert_eq!(None, Palette::parse("000000,FFFFFF,888888"));
+    assert_eq!(None, Palette::parse("000000,FFFFFG"));
+    assert_eq!(None, Palette::parse("000000,FFF"));
+}
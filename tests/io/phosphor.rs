@@ -0,0 +1,69 @@
+use chip8::cpu::CPUEnvironment;
+use chip8::io::palette::Palette;
+use chip8::io::phosphor::{Phosphor, PhosphorMode};
+
+fn red(phosphor: &Phosphor, x: usize) -> u8 {
+    phosphor.pixels()[x * 3]
+}
+
+#[test]
+pub fn phosphor_off() {
+    let mut env = CPUEnvironment::new(64, 32);
+    let mut phosphor = Phosphor::new(PhosphorMode::Off, Palette::default());
+
+    env.set_pixel(1, 0, true);
+    assert!(phosphor.update(&env));
+    assert_eq!(64 * 32 * 3, phosphor.pixels().len());
+    assert_eq!((0, 255), (red(&phosphor, 0), red(&phosphor, 1)));
+    assert!(!phosphor.update(&env));
+
+    env.set_pixel(1, 0, false);
+    assert!(phosphor.update(&env));
+    assert_eq!(0, red(&phosphor, 1));
+}
+
+#[test]
+pub fn phosphor_blend() {
+    let mut env = CPUEnvironment::new(64, 32);
+    let mut phosphor = Phosphor::new(PhosphorMode::Blend, Palette::default());
+
+    env.set_pixel(1, 0, true);
+    phosphor.update(&env);
+
+    // A sprite erased and redrawn elsewhere shows in both places for a frame.
+    env.set_pixel(1, 0, false);
+    env.set_pixel(2, 0, true);
+    assert!(phosphor.update(&env));
+    assert_eq!((255, 255), (red(&phosphor, 1), red(&phosphor, 2)));
+
+    assert!(phosphor.update(&env));
+    assert_eq!((0, 255), (red(&phosphor, 1), red(&phosphor, 2)));
+    assert!(!phosphor.update(&env));
+}
+
+#[test]
+pub fn phosphor_fade() {
+    let mut env = CPUEnvironment::new(64, 32);
+    let mut phosphor = Phosphor::new(PhosphorMode::Fade, Palette::default());
+
+    env.set_pixel(1, 0, true);
+    phosphor.update(&env);
+    assert_eq!(255, red(&phosphor, 1));
+
+    env.set_pixel(1, 0, false);
+    let mut previous = 255;
+
+    for _ in 0..4 {
+        assert!(phosphor.update(&env));
+        assert!(red(&phosphor, 1) < previous && red(&phosphor, 1) > 0);
+        previous = red(&phosphor, 1);
+    }
+
+    while phosphor.update(&env) {}
+    assert_eq!(0, red(&phosphor, 1));
+
+    // Lighting up is immediate.
+    env.set_pixel(1, 0, true);
+    phosphor.update(&env);
+    assert_eq!(255, red(&phosphor, 1));
+}
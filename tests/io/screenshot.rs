@@ -0,0 +1,66 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use chip8::cpu::CPUEnvironment;
+use chip8::io::palette::Palette;
+use chip8::io::screenshot;
+
+#[test]
+pub fn screenshot_png() {
+    let mut env = CPUEnvironment::new(64, 32);
+    env.set_pixel(0, 0, true);
+
+    let png = screenshot::png(&env, &Palette::default(), 1);
+    assert_eq!(b"\x89PNG\r\n\x1a\n", &png[..8]);
+    assert_eq!(b"IHDR", &png[12..16]);
+    assert_eq!(&[0, 0, 0, 64, 0, 0, 0, 32, 8, 2], &png[16..26]);
+    assert_eq!(b"IEND", &png[png.len() - 8..png.len() - 4]);
+
+    // IDAT: zlib header, one stored block holding 32 rows of a filter byte and 64 RGB pixels.
+    let raw_len = 32 * (1 + 64 * 3);
+    assert_eq!(b"IDAT", &png[37..41]);
+    assert_eq!(&[0x78, 0x01, 0x01], &png[41..44]);
+    assert_eq!(&(raw_len as u16).to_le_bytes(), &png[44..46]);
+    assert_eq!(&[0, 0xFF, 0xFF, 0xFF, 0], &png[48..53]);
+
+    let png = screenshot::png(&env, &Palette::default(), 4);
+    assert_eq!(&[0, 0, 1, 0, 0, 0, 0, 128, 8, 2], &png[16..26]);
+}
+
+#[test]
+pub fn screenshot_rgb() {
+    let mut env = CPUEnvironment::new(64, 32);
+    env.set_pixel(1, 0, true);
+    env.set_plane_pixel(1, 0, 1, true);
+
+    let palette = Palette::parse("000000,FFFFFF,FF0000,00FF00").unwrap();
+    let rgb = screenshot::rgb(&env, &palette, 2);
+    let pixel = |x: usize, y: usize| &rgb[(y * 128 + x) * 3..(y * 128 + x) * 3 + 3];
+
+    assert_eq!(128 * 64 * 3, rgb.len());
+    assert_eq!(&[0, 0, 0], pixel(1, 1));
+    assert_eq!(&[0xFF, 0xFF, 0xFF], pixel(2, 0));
+    assert_eq!(&[0xFF, 0xFF, 0xFF], pixel(3, 1));
+    assert_eq!(&[0xFF, 0, 0], pixel(0, 2));
+    assert_eq!(&[0xFF, 0, 0], pixel(1, 3));
+    assert_eq!(&[0, 0, 0], pixel(4, 0));
+}
+
+#[test]
+pub fn screenshot_text() {
+    let mut env = CPUEnvironment::new(64, 32);
+    env.set_pixel(1, 0, true);
+    env.set_plane_pixel(0, 2, 0, true);
+    env.set_plane_pixel(1, 2, 0, true);
+
+    let text = screenshot::text(&env);
+    assert_eq!(32, text.lines().count());
+    assert_eq!(".#3.", &text.lines().next().unwrap()[..4]);
+}
+
+#[test]
+pub fn screenshot_file_name() {
+    assert_eq!("screenshot-19700101-000000-000.png", screenshot::file_name(UNIX_EPOCH));
+
+    let time = UNIX_EPOCH + Duration::from_millis(1_709_251_199_042);
+    assert_eq!("screenshot-20240229-235959-042.png", screenshot::file_name(time));
+}
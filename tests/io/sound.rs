@@ -0,0 +1,58 @@
+use chip8::cpu::CPUEnvironment;
+use chip8::io::sound::{Synth, Tone, Waveform};
+
+fn synth(waveform: Waveform) -> Synth {
+    // 4 samples per cycle.
+    Synth::new(Tone { frequency: 1000.0, waveform, volume: 0.5 }, 4000)
+}
+
+#[test]
+pub fn sound_silent() {
+    let env = CPUEnvironment::new(64, 32);
+    let mut synth = synth(Waveform::Square);
+    let mut out = [1.0; 8];
+
+    synth.update(0, &env);
+    synth.fill(&mut out);
+    assert!(!synth.is_playing());
+    assert_eq!([0.0; 8], out);
+}
+
+#[test]
+pub fn sound_waveforms() {
+    let env = CPUEnvironment::new(64, 32);
+    let mut out = [0.0; 8];
+
+    let mut square = synth(Waveform::Square);
+    square.update(5, &env);
+    square.fill(&mut out);
+    assert_eq!([0.5, 0.5, -0.5, -0.5, 0.5, 0.5, -0.5, -0.5], out);
+
+    let mut triangle = synth(Waveform::Triangle);
+    triangle.update(5, &env);
+    triangle.fill(&mut out);
+    assert_eq!([-0.5, 0.0, 0.5, 0.0, -0.5, 0.0, 0.5, 0.0], out);
+
+    let mut sine = synth(Waveform::Sine);
+    sine.update(5, &env);
+    sine.fill(&mut out[..4]);
+    let expected = [0.0, 0.5, 0.0, -0.5];
+    assert!(out.iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-5));
+
+    assert_eq!(Some(Waveform::Triangle), Waveform::from_name("Triangle"));
+    assert_eq!(None, Waveform::from_name("sawtooth"));
+}
+
+#[test]
+pub fn sound_pattern() {
+    let mut env = CPUEnvironment::new(64, 32);
+    env.audio_pattern[0] = 0b1010_0000;
+    env.audio_pitch = 64;
+
+    // At pitch 64 the pattern plays at 4000 bits per second, one bit per sample.
+    let mut synth = synth(Waveform::Sine);
+    synth.update(1, &env);
+    let mut out = [0.0; 4];
+    synth.fill(&mut out);
+    assert_eq!([0.5, -0.5, 0.5, -0.5], out);
+}
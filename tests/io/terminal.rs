@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use chip8::cpu::{CPU, CPUEnvironment};
+use chip8::io::palette::Palette;
+use chip8::io::phosphor::{Phosphor, PhosphorMode};
+use chip8::io::terminal::{self, HeldKeys};
+
+#[test]
+pub fn terminal_half_blocks() {
+    let mut env = CPUEnvironment::new(64, 32);
+    env.set_pixel(0, 0, true);
+    env.set_pixel(1, 1, true);
+
+    let mut phosphor = Phosphor::new(PhosphorMode::Off, Palette::default());
+    phosphor.update(&env);
+
+    let lines = terminal::half_blocks(phosphor.pixels(), 64);
+    assert_eq!(16, lines.len());
+
+    let white = "\x1b[38;2;255;255;255m";
+    let black = "\x1b[38;2;0;0;0m";
+    let on_white = "\x1b[48;2;255;255;255m";
+    let on_black = "\x1b[48;2;0;0;0m";
+
+    assert_eq!(format!("{}{}▀{}{}▀{}{}\x1b[0m", white, on_black, black, on_white, on_black, "▀".repeat(62)), lines[0]);
+    assert_eq!(format!("{}{}{}\x1b[0m", black, on_black, "▀".repeat(64)), lines[1]);
+}
+
+#[test]
+pub fn terminal_register_panel() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[0x6A, 0x42]);
+    cpu.regs.set_v(0xA, 0x42).unwrap();
+    cpu.regs.i = 0x123;
+
+    let panel = terminal::register_panel(&cpu);
+
+    assert_eq!("V0 00     V8 00", panel[0]);
+    assert_eq!("V2 00     VA 42", panel[2]);
+    assert_eq!("PC 0200   I 0123", panel[9]);
+    assert_eq!("6A42 LD VA, 0x42", panel[13]);
+    assert!(panel.iter().all(|line| line.len() <= terminal::PANEL_WIDTH));
+}
+
+#[test]
+pub fn terminal_key_names() {
+    let names = |input: &[u8]| terminal::key_names(input);
+
+    assert_eq!(vec!["Q", "Q", "1", "Space", "Return", "Tab", "Backspace"], names(b"qQ1 \r\t\x7f"));
+    assert_eq!(vec!["Ctrl+C"], names(b"\x03"));
+    assert_eq!(vec!["Up", "Left", "Right"], names(b"\x1b[A\x1bOD\x1b[1;5C"));
+    assert_eq!(vec!["F1", "F5", "F12", "Delete"], names(b"\x1bOP\x1b[15~\x1b[24;2~\x1b[3~"));
+    assert_eq!(vec!["F2", "F5"], names(b"\x1b[[B\x1b[[E"));
+    assert_eq!(vec!["Escape"], names(b"\x1b"));
+    assert_eq!(vec!["A"], names(b"\x1b[99~a\xc3\xa9"));
+}
+
+#[test]
+pub fn terminal_held_keys() {
+    let ms = Duration::from_millis;
+    let mut held = HeldKeys::new(ms(100), ms(100));
+
+    assert!(held.press("Q", ms(0)));
+    assert!(held.press("W", ms(10)));
+    assert!(!held.press("Q", ms(50)));
+    assert!(held.release(ms(100)).is_empty());
+    assert_eq!(vec!["W"], held.release(ms(110)));
+    assert_eq!(vec!["Q"], held.release(ms(150)));
+    assert!(held.press("Q", ms(160)));
+}
+
+#[test]
+pub fn terminal_held_keys_repeat() {
+    let ms = Duration::from_millis;
+    let mut held = HeldKeys::new(ms(500), ms(150));
+
+    // Held until the first repeat at 450ms, then repeated every 30ms.
+    assert!(held.press("Q", ms(0)));
+    assert!(held.release(ms(440)).is_empty());
+
+    for time in (450..=600).step_by(30) {
+        assert!(!held.press("Q", ms(time)));
+        assert!(held.release(ms(time + 20)).is_empty());
+    }
+
+    assert!(held.release(ms(749)).is_empty());
+    assert_eq!(vec!["Q"], held.release(ms(750)));
+}
@@ -1,3 +1,11 @@
 extern crate chip8;
 
-mod cpu;
\ No newline at end of file
+mod cpu;
+mod disasm;
+mod asm;
+mod debug;
+mod rewind;
+mod movie;
+mod headless;
+mod io;
+mod scheduler;
\ No newline at end of file
@@ -0,0 +1,77 @@
+use chip8::cpu::CPU;
+use chip8::cpu::quirks::Quirks;
+use chip8::movie::{rom_hash, InputEvent, Movie, Player, Recorder};
+
+const PROGRAM: [u8; 12] = [
+    0xF0, 0x0A, // 0200 - LD V0, K
+    0xC1, 0xFF, // 0202 - RND V1, 0xFF
+    0xE0, 0x9E, // 0204 - SKP V0
+    0x12, 0x04, // 0206 - JP 0x204
+    0x72, 0x01, // 0208 - ADD V2, 0x01
+    0x12, 0x08, // 020A - JP 0x208
+];
+
+/// Runs one instruction per frame. Input comes from the player if there is
+/// one, otherwise key 5 is held for frames 3 to 5 and recorded.
+fn run(cpu: &mut CPU, frames: u32, mut player: Option<&mut Player>, mut recorder: Option<&mut Recorder>) {
+    for frame in 0..frames {
+        if let Some(player) = &mut player {
+            player.apply(frame, cpu);
+        }
+
+        if let Some(recorder) = &mut recorder {
+            match frame {
+                3 => { cpu.env.keyboard[0x5] = true; cpu.press_key(0x5); },
+                6 => cpu.env.keyboard[0x5] = false,
+                _ => ()
+            }
+
+            recorder.record(frame, &cpu.env.keyboard);
+        }
+
+        cpu.step().unwrap();
+        cpu.tick();
+    }
+}
+
+#[test]
+pub fn movie_record_and_play() {
+    let mut cpu = CPU::with_quirks(Quirks::default());
+    cpu.mem.load_program(&PROGRAM);
+    let mut recorder = Recorder::new(cpu.seed(), &PROGRAM, 1);
+    run(&mut cpu, 20, None, Some(&mut recorder));
+
+    let movie = recorder.finish(20);
+    assert_eq!(rom_hash(&PROGRAM), movie.rom_hash);
+    assert_eq!(vec![InputEvent { frame: 3, keys: 0x20 }, InputEvent { frame: 6, keys: 0 }], movie.events);
+
+    let movie = Movie::parse(&movie.to_string()).unwrap();
+    let mut replay = CPU::new();
+    replay.reseed(movie.seed);
+    replay.mem.load_program(&PROGRAM);
+    let mut player = Player::new(movie);
+    run(&mut replay, 20, Some(&mut player), None);
+
+    assert!(player.is_finished(20));
+    assert_eq!(cpu.save_state(), replay.save_state());
+    assert_eq!(Some(5), replay.regs.v(0));
+}
+
+#[test]
+pub fn movie_format() {
+    let text = "CHIP8MOVIE 2\nseed 00000000000004d2\nrom 00000000deadbeef\nlength 100\nipf 15\n3 0020\n6 0000\n";
+    let movie = Movie::parse(text).unwrap();
+
+    assert_eq!(1234, movie.seed);
+    assert_eq!(0xDEADBEEF, movie.rom_hash);
+    assert_eq!(100, movie.length);
+    assert_eq!(15, movie.instructions_per_frame);
+    assert_eq!(2, movie.events.len());
+    assert_eq!(text, movie.to_string());
+
+    assert_eq!(1, Movie::parse("CHIP8MOVIE 1\nseed 0\nrom 0\nlength 9\n5 0001\n").unwrap_err().line);
+    assert_eq!(1, Movie::parse("CHIP8MOVIE 3\n").unwrap_err().line);
+    assert_eq!(5, Movie::parse("CHIP8MOVIE 2\nseed 0\nrom 0\nlength 9\n5 0001\n").unwrap_err().line);
+    assert_eq!(3, Movie::parse("CHIP8MOVIE 2\nseed 0\nrom xyz\n").unwrap_err().line);
+    assert_eq!(7, Movie::parse("CHIP8MOVIE 2\nseed 0\nrom 0\nlength 9\nipf 1\n5 0001\n4 0000\n").unwrap_err().line);
+}
@@ -0,0 +1,93 @@
+use chip8::cpu::CPU;
+use chip8::rewind::{self, Rewind};
+
+#[test]
+pub fn rewind_cpu_states() {
+    let mut cpu = CPU::new();
+    cpu.mem.load_program(&[
+        0x70, 0x01, // 0200 - ADD V0, 0x01
+        0xA3, 0x00, // 0202 - LD I, 0x300
+        0xF0, 0x55, // 0204 - LD [I], V0
+        0x12, 0x00, // 0206 - JP 0x200
+    ]);
+
+    let mut rewind = Rewind::new(100);
+    let mut states = Vec::new();
+
+    for _ in 0..40 {
+        cpu.step().unwrap();
+        states.push(cpu.save_state());
+        rewind.push(cpu.save_state());
+    }
+
+    assert_eq!(40, rewind.len());
+    assert!(rewind.memory_usage() < states[0].len() * 2);
+
+    for expected in states.iter().rev().skip(1) {
+        assert_eq!(Some(expected), rewind.pop().as_ref());
+    }
+
+    assert_eq!(None, rewind.pop());
+    assert_eq!(1, rewind.len());
+
+    cpu.load_state(&states[0]).unwrap();
+    assert_eq!(0x202, cpu.regs.pc);
+    assert_eq!(Some(1), cpu.regs.v(0));
+}
+
+#[test]
+pub fn rewind_capacity() {
+    let mut rewind = Rewind::new(3);
+
+    for n in 0..5u8 {
+        rewind.push(vec![n; 4]);
+    }
+
+    assert_eq!(3, rewind.len());
+    assert_eq!(Some(vec![3; 4]), rewind.pop());
+    assert_eq!(Some(vec![2; 4]), rewind.pop());
+    assert_eq!(None, rewind.pop());
+
+    rewind.push(vec![9; 4]);
+    assert_eq!(Some(vec![2; 4]), rewind.pop());
+
+    rewind.clear();
+    assert!(rewind.is_empty());
+
+    let mut rewind = Rewind::new(1);
+
+    for n in 0..3u8 {
+        rewind.push(vec![n; 4]);
+    }
+
+    assert_eq!(1, rewind.len());
+    assert_eq!(None, rewind.pop());
+}
+
+#[test]
+pub fn rewind_size_change() {
+    let mut lores = CPU::new();
+    let state = lores.save_state();
+
+    lores.execute(0x00FF).unwrap(); // HIGH
+    let mut rewind = Rewind::new(10);
+    rewind.push(state.clone());
+    rewind.push(lores.save_state());
+
+    assert_eq!(Some(state), rewind.pop());
+}
+
+#[test]
+pub fn rewind_invalid_delta() {
+    let delta = rewind::diff(&[1, 2, 3], &[1, 5, 3, 4]);
+    assert_eq!(Some(vec![1, 5, 3, 4]), rewind::apply(&[1, 2, 3], &delta));
+
+    // Cut off in the middle of the changed bytes.
+    assert_eq!(None, rewind::apply(&[1, 2, 3], &delta[..delta.len() - 1]));
+    // Changes a byte past the end of the state.
+    assert_eq!(None, rewind::apply(&[1, 2, 3], &[3, 10, 1, 0xFF]));
+    // Offsets that overflow.
+    assert_eq!(None, rewind::apply(&[1, 2, 3], &[3, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x01, 1, 0xFF]));
+    assert_eq!(None, rewind::apply(&[1, 2, 3], &[0xFF; 12]));
+    assert_eq!(None, rewind::apply(&[1, 2, 3], &[0x80]));
+}
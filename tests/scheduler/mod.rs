@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use chip8::scheduler::{Scheduler, FRAME_RATE, MAX_CATCH_UP_FRAMES, MAX_SPEED};
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+pub fn scheduler_frame_rate() {
+    let mut scheduler = Scheduler::new(ms(0));
+    let mut frames = 0;
+
+    // Polling at an uneven rate, like a 144 Hz display with jitter.
+    for t in (0..1000).step_by(7) {
+        frames += scheduler.frames_due(ms(t));
+    }
+
+    frames += scheduler.frames_due(ms(999));
+    assert_eq!(FRAME_RATE, frames);
+    assert_eq!(0, scheduler.frames_due(ms(999)));
+    assert_eq!(1, scheduler.frames_due(ms(1000)));
+}
+
+#[test]
+pub fn scheduler_wait() {
+    let mut scheduler = Scheduler::new(ms(100));
+    assert_eq!(1, scheduler.frames_due(ms(100)));
+
+    let wait = scheduler.time_until_next_frame(ms(105));
+    assert!(wait > ms(11) && wait < ms(12));
+    assert_eq!(Duration::from_secs(0), scheduler.time_until_next_frame(ms(200)));
+}
+
+#[test]
+pub fn scheduler_catch_up() {
+    let mut scheduler = Scheduler::new(ms(0));
+    assert_eq!(MAX_CATCH_UP_FRAMES, scheduler.frames_due(ms(5000)));
+    assert_eq!(0, scheduler.frames_due(ms(5010)));
+    assert_eq!(1, scheduler.frames_due(ms(5017)));
+
+    scheduler.reset(ms(9000));
+    assert_eq!(1, scheduler.frames_due(ms(9000)));
+}
+
+#[test]
+pub fn scheduler_speed() {
+    let mut scheduler = Scheduler::new(ms(0));
+    scheduler.set_speed(2.0);
+
+    let frames: u32 = (0..=1000).map(|t| scheduler.frames_due(ms(t))).sum();
+    assert_eq!(2 * FRAME_RATE + 1, frames);
+
+    scheduler.set_speed(1000.0);
+    assert_eq!(MAX_SPEED, scheduler.speed());
+}